@@ -1,10 +1,11 @@
 use std::time::Duration;
 use criterion::{criterion_group, criterion_main, Criterion};
 
-use simple_sudoku_generator::bench_main;
+use simple_sudoku_generator::{bench_main, bench_solve};
 
 fn criterion_benchmark(c: &mut Criterion) {
-    c.bench_function("Sudoku main", |b| b.iter(|| bench_main()));
+    c.bench_function("Sudoku main", |b| b.iter(bench_main));
+    c.bench_function("Sudoku solve", |b| b.iter(bench_solve));
 }
 
 criterion_group!{
@@ -0,0 +1,301 @@
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::cell::digit_to_char;
+use crate::history::EntryType;
+use crate::layout::Layout;
+use crate::random;
+use crate::sudoku::{Rating, Sudoku, Symmetry};
+
+// Generates many puzzles at once, spread over several worker threads.
+//
+// Every attempt gets its own seed, and the worker seeds its generator
+// with it right before generating (see random.rs). So any puzzle can be
+// made again on its own with generate_puzzle and the seed it came with,
+// no matter which worker made it or how many workers there were.
+//
+// Attempts that end up with an invalid board are skipped, which is why
+// the seeds of a batch can have gaps. Puzzles are handed over as soon as
+// they are done, so they don't come in the order of their seeds.
+//
+// A batch can be written as bare puzzle strings (write_batch), or as a
+// catalog (write_catalog) that keeps the solution, the seed and the
+// rating of each puzzle alongside it.
+
+
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    pub count: usize,
+    pub threads: usize,
+    pub seed: u64,
+    pub symmetry: Symmetry,
+    pub layout: Arc<Layout>,
+}
+
+/* Structure:
+    count:
+        the amount of puzzles to generate.
+
+    threads:
+        the amount of worker threads, 0 uses one per core.
+
+    seed:
+        the seed of the first attempt, the next ones count up from it.
+
+    symmetry:
+        the pattern the clues of each puzzle are removed in.
+
+    layout:
+        the layout of every board of the batch.
+*/
+
+#[derive(Debug)]
+pub struct BatchPuzzle {
+    pub seed: u64,
+    pub puzzle: Sudoku,
+    pub solution: Vec<usize>,
+    pub rating: Rating,
+}
+
+/* Structure:
+    seed:
+        the seed the puzzle was generated from.
+
+    puzzle:
+        the puzzle as a board with only its givens solved.
+
+    solution:
+        the only solution of the puzzle, as the digit of each cell.
+
+    rating:
+        how the rules did on the puzzle, see Sudoku::rate.
+*/
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CatalogFormat {
+    Csv,
+    JsonLines,
+}
+
+/* Structure:
+    Csv:
+        a header line, then one line per puzzle with the columns
+        puzzle, solution, seed, clues, difficulty and then the amount of
+        steps of each entry type (in the order of EntryType::ALL).
+        The difficulty is left empty if the rules can't solve the puzzle.
+
+    JsonLines:
+        one JSON object per line with the keys puzzle, solution, seed,
+        clues, difficulty (null if the rules can't solve the puzzle) and
+        techniques, which maps the name of each entry type that was used
+        to its amount of steps. The seed is a string, since most JSON
+        readers can't hold every u64 as a number.
+*/
+
+
+impl BatchConfig {
+    // count standard puzzles with a random starting seed
+    pub fn new(count: usize) -> Self {
+        Self {
+            count,
+            threads: 0,
+            seed: rand::random(),
+            symmetry: Symmetry::None,
+            layout: Layout::shared_standard(),
+        }
+    }
+
+    fn worker_count(&self) -> usize {
+        if self.threads > 0 {
+            return self.threads;
+        }
+
+        thread::available_parallelism().map_or(1, |n| n.get())
+    }
+}
+
+
+// The puzzle made from seed, or None if the board generated from it
+// turned out invalid.
+pub fn generate_puzzle(seed: u64, layout: &Arc<Layout>,
+                       symmetry: Symmetry) -> Option<BatchPuzzle> {
+    random::set_seed(seed);
+
+    let sud = Sudoku::fill_incremental_with(Arc::clone(layout));
+
+    if !sud.is_valid_solution() {
+        return None;
+    }
+
+    let puzzle = sud.remove_clues(symmetry);
+
+    Some(BatchPuzzle {
+        seed,
+        rating: puzzle.rate(),
+        puzzle,
+        solution: sud.to_solutions(),
+    })
+}
+
+// Generates config.count puzzles and hands each one to on_puzzle as soon
+// as it's done, on the thread that called this. Returns the amount of
+// attempts that were skipped for being invalid.
+//
+// This keeps going until it has enough puzzles, so a layout whose boards
+// are never valid never returns.
+pub fn generate_batch(config: &BatchConfig,
+                      mut on_puzzle: impl FnMut(BatchPuzzle)) -> usize {
+    if config.count == 0 {
+        return 0;
+    }
+
+    let next_seed = AtomicU64::new(config.seed);
+    let done      = AtomicBool::new(false);
+
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..config.worker_count() {
+            let sender = sender.clone();
+            let (next_seed, done) = (&next_seed, &done);
+
+            scope.spawn(move || {
+                while !done.load(Ordering::Relaxed) {
+                    let seed = next_seed.fetch_add(1, Ordering::Relaxed);
+
+                    let p = generate_puzzle(seed, &config.layout, config.symmetry);
+
+                    if sender.send(p).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        drop(sender);
+
+        let mut made    = 0;
+        let mut skipped = 0;
+
+        for p in receiver.iter() {
+            match p {
+                Some(p) => {
+                    on_puzzle(p);
+                    made += 1;
+                },
+                None => skipped += 1,
+            }
+
+            if made == config.count {
+                break;
+            }
+        }
+
+        // Workers stop at their next attempt, whatever they are still
+        // working on is dropped along with the receiver.
+        done.store(true, Ordering::Relaxed);
+        drop(receiver);
+
+        skipped
+    })
+}
+
+// Generates a batch and writes each puzzle to out as it comes in, one
+// puzzle string (see Sudoku::to_puzzle_str) per line. Nothing more is
+// written after the first error, which is returned once the batch is done.
+pub fn write_batch(config: &BatchConfig, out: &mut impl Write) -> io::Result<()> {
+    write_each(config, out, |p, out| writeln!(out, "{}", p.puzzle.to_puzzle_str()))
+}
+
+// Same as write_batch, with every puzzle written as a catalog entry in
+// the given format.
+pub fn write_catalog(config: &BatchConfig, format: CatalogFormat,
+                     out: &mut impl Write) -> io::Result<()> {
+    if format == CatalogFormat::Csv {
+        write_csv_header(out)?;
+    }
+
+    write_each(config, out, |p, out| write_catalog_entry(p, format, out))
+}
+
+// Writes one puzzle as a single line of the format
+pub fn write_catalog_entry(p: &BatchPuzzle, format: CatalogFormat,
+                           out: &mut impl Write) -> io::Result<()> {
+    let puzzle   = p.puzzle.to_puzzle_str();
+    let solution = solution_str(&p.solution);
+    let clues    = p.puzzle.clue_count();
+
+    match format {
+        CatalogFormat::Csv => {
+            let difficulty = p.rating.difficulty
+                .map_or(String::new(), |d| d.to_string());
+
+            write!(out, "{puzzle},{solution},{},{clues},{difficulty}", p.seed)?;
+
+            for count in p.rating.techniques {
+                write!(out, ",{count}")?;
+            }
+
+            writeln!(out)
+        },
+        CatalogFormat::JsonLines => {
+            let difficulty = p.rating.difficulty
+                .map_or("null".to_string(), |d| d.to_string());
+
+            // Puzzle strings only hold digits, letters and dots, and the
+            // names of the entry types are plain identifiers, so nothing
+            // needs escaping.
+            write!(out, "{{\"puzzle\":\"{puzzle}\",\"solution\":\"{solution}\",\
+                         \"seed\":\"{}\",\"clues\":{clues},\
+                         \"difficulty\":{difficulty},\"techniques\":{{", p.seed)?;
+
+            let used = EntryType::ALL.iter()
+                .zip(p.rating.techniques)
+                .filter(|&(_, count)| count > 0);
+
+            for (i, (t, count)) in used.enumerate() {
+                let sep = if i == 0 { "" } else { "," };
+
+                write!(out, "{sep}\"{t:?}\":{count}")?;
+            }
+
+            writeln!(out, "}}}}")
+        },
+    }
+}
+
+fn write_csv_header(out: &mut impl Write) -> io::Result<()> {
+    write!(out, "puzzle,solution,seed,clues,difficulty")?;
+
+    for t in EntryType::ALL {
+        write!(out, ",{t:?}")?;
+    }
+
+    writeln!(out)
+}
+
+// Digits of the solution in the same format as a puzzle string
+fn solution_str(solution: &[usize]) -> String {
+    solution.iter().map(|&d| digit_to_char(d)).collect()
+}
+
+// Generates a batch and writes each puzzle to out with write_puzzle,
+// stopping at the first error like write_batch.
+fn write_each<W: Write>(config: &BatchConfig, out: &mut W,
+                        mut write_puzzle: impl FnMut(&BatchPuzzle, &mut W) -> io::Result<()>)
+                        -> io::Result<()> {
+    let mut result = Ok(());
+
+    generate_batch(config, |p| {
+        if result.is_ok() {
+            result = write_puzzle(&p, out);
+        }
+    });
+
+    result?;
+
+    out.flush()
+}
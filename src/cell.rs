@@ -1,60 +1,102 @@
 use rand::Rng;
 use std::fmt;
-use std::ops::RangeInclusive;
+
+use crate::random;
 
 /* Structure:
     bit 0: if set, cell is considered to be solved
         there should only be one digit set or none
-        this also controls the meaning of bits 10-13:
+        this also controls the meaning of bits 26-30:
             if bit 0 is set, they signify the number and the count is 1 or 0
             if unset, they signify the count of digits and the number is 0
-    bit 1-9: cell can have numbers 1-9
-    bit 10-13: the selected number in binary,
+    bit 1-25: cell can have numbers 1-25
+        boards smaller than 25x25 only use the first few of these
+    bit 26-30: the selected number in binary,
                or the count of the digits
-        this should never have a value above decimal 9,
-            except for 0b1111, which means the count is to be ignored.
+        this should never have a value above decimal 25,
+            except for 0b11111, which means the count is to be ignored.
                 This only applies when the solved bit is not set.
         zero means no valid digit or no selected digit
-    bit 14-15: unused
+    bit 31: if set, the cell is a given (a clue of the puzzle) instead of
+        being solved from the other cells, only solved cells are given
 */
-pub type CellSize = u16;
+pub type CellSize = u32;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Cell(CellSize);
 
 // I actually don't know if Windows is lil-endian or big-endian.
 // I also don't care.
-const SOLUTION_MASK: CellSize = 0b00000000_00000001;
-const DIGIT_MASK:    CellSize = 0b00000011_11111110;
-const NUMBER_MASK:   CellSize = 0b00111100_00000000;
+const SOLUTION_MASK: CellSize = 0b00000000_00000000_00000000_00000001;
+const DIGIT_MASK:    CellSize = 0b00000011_11111111_11111111_11111110;
+const NUMBER_MASK:   CellSize = 0b01111100_00000000_00000000_00000000;
 const COUNT_MASK:    CellSize = NUMBER_MASK;
-const _UNUSED_MASK:  CellSize = 0b11000000_00000000;
+const GIVEN_MASK:    CellSize = 0b10000000_00000000_00000000_00000000;
+
+// The most digits a cell can have, enough for a 25x25 board
+pub const MAX_DIGIT: usize = 25;
 
-pub const DIGIT_RANGE: RangeInclusive<usize> = 1..=9;
 #[allow(non_snake_case)]
 fn DIGIT(x: usize) -> CellSize {
     debug_assert!(
-        *DIGIT_RANGE.start() <= x && x <= *DIGIT_RANGE.end(),
+        (1..=MAX_DIGIT).contains(&x),
         "{x} is not a valid digit!"
     );
     
     1 << x
 }
 
-const NUM_SHIFT:    u16 = 10;
-const COUNT_SHIFT:  u16 = NUM_SHIFT;
-const IGNORE_COUNT: u16 = COUNT_MASK;
+const NUM_SHIFT:    u32 = 26;
+const COUNT_SHIFT:  u32 = NUM_SHIFT;
+const IGNORE_COUNT: u32 = COUNT_MASK;
 
-pub const CELL_INIT:  Cell = Cell(DIGIT_MASK | (9 << COUNT_SHIFT));
+// Every digit of a standard 9x9 board, see Cell::with_digits for others
+pub const CELL_INIT:  Cell = Cell((DIGIT_MASK & 0b11_1111_1110) | (9 << COUNT_SHIFT));
 pub const CELL_EMPTY: Cell = Cell(0);
 pub const CELL_ACC:   Cell = Cell(IGNORE_COUNT);
 
 
+// Digits above 9 are written as letters, 10 is 'A' and 25 is 'P'.
+pub fn digit_to_char(d: usize) -> char {
+    debug_assert!(d <= MAX_DIGIT, "{d} is not a valid digit!");
+
+    match d {
+        0..=9 => (b'0' + d as u8) as char,
+        _     => (b'A' + (d - 10) as u8) as char,
+    }
+}
+
+pub fn char_to_digit(ch: char) -> Option<usize> {
+    let d = match ch {
+        '0'..='9' => ch as usize - '0' as usize,
+        'A'..='P' => ch as usize - 'A' as usize + 10,
+        'a'..='p' => ch as usize - 'a' as usize + 10,
+        _ => return None,
+    };
+
+    Some(d)
+}
+
+
 impl Cell {
+    // An unsolved cell that can have every digit from 1 to size
+    pub fn with_digits(size: usize) -> Cell {
+        debug_assert!(size <= MAX_DIGIT, "Invalid board size: {size}");
+
+        let digits = (((1 as CellSize) << size) - 1) << 1;
+
+        Cell(digits | ((size as CellSize) << COUNT_SHIFT))
+    }
+
+    // The opposite of get_digits, the count of the cell is off
+    pub fn from_digits(digits: CellSize) -> Cell {
+        Cell(((digits << 1) & DIGIT_MASK) | IGNORE_COUNT)
+    }
+
     pub fn get_number(&self) -> usize {
         if self.is_solved() {
             // TODO: Add check for value?
-            ((self.0 & NUMBER_MASK) >> NUM_SHIFT).into()
+            ((self.0 & NUMBER_MASK) >> NUM_SHIFT) as usize
         }
         else {
             0
@@ -97,7 +139,7 @@ impl Cell {
         // According to rust compiler, c must be >= 0 b/c of its type
         // so checking it is pointless. However, it should be noted
         // that c should be >= 0.
-        debug_assert!(c <= 9, "Invalid value of count: {c}");
+        debug_assert!(c as usize <= MAX_DIGIT, "Invalid value of count: {c}");
 
         self.0 = (self.0 & !COUNT_MASK) | (c << COUNT_SHIFT);
     }
@@ -105,14 +147,7 @@ impl Cell {
     pub fn reset_count(&mut self) {
         // Cell should be unsolved (guaranteed by calling set_count)
 
-        let mut c = 0;
-        for d in DIGIT_RANGE {
-            if self.has_digit(d) {
-                c += 1;
-            }
-        }
-
-        self.set_count(c);
+        self.set_count((self.0 & DIGIT_MASK).count_ones());
     }
 
     // In set terms, the complement
@@ -125,7 +160,7 @@ impl Cell {
             Cell(c.0 | IGNORE_COUNT)
         }
         else {
-            c.set_count(9 - c.get_count());
+            c.reset_count();
             c
         }
     }
@@ -198,6 +233,18 @@ impl Cell {
         (self.0 & SOLUTION_MASK) != 0
     }
 
+    // Givens stay given through solving, none of the set operations
+    // touch this bit.
+    pub fn set_given(&mut self) {
+        debug_assert!(self.is_solved(), "Only solved cells can be given");
+
+        self.0 |= GIVEN_MASK;
+    }
+
+    pub fn is_given(&self) -> bool {
+        (self.0 & GIVEN_MASK) != 0
+    }
+
     pub fn get_unsolved_copy(&self) -> Cell {
         let mut c = Cell(self.0);
 
@@ -214,6 +261,27 @@ impl Cell {
         c
     }
 
+    // The same cell with each digit d replaced by digits[d], digits[0]
+    // is unused. The count and the given flag stay the same.
+    pub fn relabel(&self, digits: &[usize]) -> Cell {
+        let mut c = Cell(self.0 & !DIGIT_MASK);
+
+        for (d, &nd) in digits.iter().enumerate().skip(1) {
+            if self.has_digit(d) {
+                c.0 |= DIGIT(nd);
+            }
+        }
+
+        let number = self.get_number();
+
+        if number != 0 {
+            c.0 = (c.0 & !NUMBER_MASK)
+                      | ((digits[number] as CellSize) << NUM_SHIFT);
+        }
+
+        c
+    }
+
     pub fn has_intersection(&self, other: Cell) -> bool {
         (self.0 & other.0) & DIGIT_MASK != 0
     }
@@ -286,11 +354,11 @@ impl Cell {
 
         let mut chosen = 0;
         let mut factor = -1.0;
-        let mut r = rand::thread_rng();
+        let mut r = random::rng();
 
         // Not sure if this is absolutely perfect,
         // but it works.
-        for i in DIGIT_RANGE {
+        for i in 1..=MAX_DIGIT {
             if self.has_digit(i) {
                 let f = r.gen_range(0.0..=1.0);
                 if f > factor {
@@ -306,6 +374,6 @@ impl Cell {
 
 impl fmt::Display for Cell {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Cell({:0>14b})", self.0)
+        write!(f, "Cell({:0>32b})", self.0)
     }
 }
\ No newline at end of file
@@ -0,0 +1,309 @@
+use crate::cell::CellSize;
+use crate::index_manip::CellIndex;
+use crate::layout::{Layout, repeats_digit};
+use crate::history::EntryType;
+
+// Markings drawn on top of a board that limit which digits a few cells
+// can have, on top of the usual sections:
+//     thermometer: the digits strictly increase from the bulb to the tip
+//     arrow:       the digits along the arrow add up to the digit in
+//                  its circle (digits on an arrow may repeat)
+//     kropki:      a white dot between two cells means their digits are
+//                  consecutive, a black dot means one is double the other
+//     parity:      the digit of the cell is even/odd
+//     less than:   the digit of the first cell is smaller than the digit
+//                  of the second, drawn as a < or > between them
+//
+// Every constraint is checked the same way, by trying to fill its cells
+// one at a time with the digits they have left, so adding a new kind of
+// constraint only needs a check for partially filled cells.
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Constraint {
+    Thermometer(Vec<CellIndex>),
+    Arrow { circle: CellIndex, arrow: Vec<CellIndex> },
+    Kropki(KropkiDot, [CellIndex; 2]),
+    Parity(Parity, CellIndex),
+    LessThan([CellIndex; 2]),
+}
+
+/* Structure:
+    Thermometer:
+        the cells of the thermometer, starting at the bulb.
+
+    Arrow:
+        the cell in the circle and the cells along the arrow.
+
+    Kropki:
+        the color of the dot and the two cells on each side of it.
+
+    Parity:
+        if the digit of the cell is even or odd, and the cell.
+
+    LessThan:
+        the cell with the smaller digit, then the one with the bigger one.
+*/
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum KropkiDot {
+    White,
+    Black,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+
+impl Constraint {
+    // The cells of the constraint, in the order they are filled in
+    // when looking for the digits they can have.
+    pub fn cells(&self) -> Vec<CellIndex> {
+        match self {
+            Constraint::Thermometer(cells) => cells.clone(),
+            Constraint::Arrow { circle, arrow } => {
+                let mut cells = vec![*circle];
+                cells.extend(arrow);
+                cells
+            },
+            Constraint::Kropki(_, cells) => cells.to_vec(),
+            Constraint::Parity(_, ci)    => vec![*ci],
+            Constraint::LessThan(cells)  => cells.to_vec(),
+        }
+    }
+
+    pub fn entry_type(&self) -> EntryType {
+        match self {
+            Constraint::Thermometer(_) => EntryType::Thermometer,
+            Constraint::Arrow { .. }   => EntryType::Arrow,
+            Constraint::Kropki(..)     => EntryType::Kropki,
+            Constraint::Parity(..)     => EntryType::Parity,
+            Constraint::LessThan(_)    => EntryType::Inequality,
+        }
+    }
+
+    // If none of the cells can ever have the same digit, which makes
+    // them peers of each other.
+    pub fn is_distinct(&self) -> bool {
+        !matches!(self, Constraint::Arrow { .. })
+    }
+
+    // If the digits of the first few cells (in the order of cells())
+    // can still be part of a way to satisfy the constraint.
+    pub fn allows(&self, digits: &[usize], size: usize) -> bool {
+        let Some(&last) = digits.last() else {
+            return true;
+        };
+
+        let i = digits.len() - 1;
+
+        match self {
+            Constraint::Thermometer(cells) => {
+                // Leave room for the rest of the thermometer
+                (i == 0 || digits[i - 1] < last)
+                    && last > i
+                    && last + (cells.len() - 1 - i) <= size
+            },
+            Constraint::Arrow { arrow, .. } => {
+                let circle = digits[0];
+                let sum: usize = digits[1..].iter().sum();
+                let left = arrow.len() + 1 - digits.len();
+
+                if left == 0 {
+                    sum == circle
+                }
+                else {
+                    sum + left <= circle && sum + left * size >= circle
+                }
+            },
+            Constraint::Kropki(dot, _) => {
+                if i == 0 {
+                    return true;
+                }
+
+                let (a, b) = (digits[0], last);
+
+                match dot {
+                    KropkiDot::White => a.abs_diff(b) == 1,
+                    KropkiDot::Black => a == 2 * b || b == 2 * a,
+                }
+            },
+            Constraint::Parity(p, _) => {
+                (last % 2 == 0) == (*p == Parity::Even)
+            },
+            Constraint::LessThan(_) => i == 0 || digits[0] < last,
+        }
+    }
+
+    // If the digits of every cell (in the order of cells()) satisfy
+    // the constraint.
+    pub fn is_satisfied_by(&self, digits: &[usize], size: usize) -> bool {
+        digits.len() == self.cells().len()
+            && (1..=digits.len()).all(|i| self.allows(&digits[..i], size))
+    }
+
+    // Per cell (in the order of cells()), the digits it has in at least
+    // one way to satisfy the constraint, as bitmasks where bit 0 is
+    // digit 1. cands gives the digits each cell can have, the same way.
+    //
+    // Cells that are peers of each other can't share a digit, even if
+    // the constraint would allow it.
+    pub fn supported_digits(&self, layout: &Layout,
+                            cands: impl Fn(CellIndex) -> CellSize)
+                                                    -> Vec<CellSize> {
+        let cells = self.cells();
+
+        let mut search = SupportSearch {
+            constraint: self,
+            layout,
+            cands: cells.iter().map(|&ci| cands(ci)).collect(),
+            digits: Vec::with_capacity(cells.len()),
+            supported: vec![0; cells.len()],
+            cells,
+        };
+
+        search.fill();
+
+        search.supported
+    }
+}
+
+
+struct SupportSearch<'a> {
+    constraint: &'a Constraint,
+    layout: &'a Layout,
+    cells: Vec<CellIndex>,
+    cands: Vec<CellSize>,
+    digits: Vec<usize>,
+    supported: Vec<CellSize>,
+}
+
+impl SupportSearch<'_> {
+    fn fill(&mut self) {
+        let i = self.digits.len();
+
+        if i == self.cells.len() {
+            for (s, &d) in self.supported.iter_mut().zip(&self.digits) {
+                *s |= 1 << (d - 1);
+            }
+
+            return;
+        }
+
+        for d in self.layout.digit_range() {
+            if self.cands[i] & (1 << (d - 1)) == 0
+                    || repeats_digit(self.layout, &self.cells, &self.digits, i, d) {
+                continue;
+            }
+
+            self.digits.push(d);
+
+            if self.constraint.allows(&self.digits, self.layout.size()) {
+                self.fill();
+            }
+
+            self.digits.pop();
+        }
+    }
+}
+
+
+// Reads constraints from text, one per line (or separated by ';'), with
+// cells written as r<row>c<col>, counting from 1:
+//
+//     even r1c1
+//     odd r1c2
+//     r1c1 < r1c2
+//     r2c1 > r3c1
+//     thermo r1c1 r2c1 r3c1
+//     arrow r5c5 r4c4 r3c3        (the circle comes first)
+//     white r1c1 r1c2
+//     black r1c1 r1c2
+//
+// Empty lines and lines starting with '#' are skipped. Returns None if
+// a line can't be read, has a cell that isn't on the board, or has an
+// inequality or kropki dot between cells that aren't next to each other
+// (there would be no line between them to draw it on). The cells of a
+// thermometer have to be a path without repeats, where each cell touches
+// the one before it, diagonals included.
+pub fn parse_constraints(s: &str, layout: &Layout) -> Option<Vec<Constraint>> {
+    let mut constraints = Vec::new();
+
+    let cell  = |w: &str| parse_cell(w, layout);
+    let cells = |ws: &[&str]| ws.iter().map(|w| cell(w)).collect::<Option<Vec<_>>>();
+
+    for line in s.split(['\n', ';']) {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        let c = match words.as_slice() {
+            ["even", a] => Constraint::Parity(Parity::Even, cell(a)?),
+            ["odd", a]  => Constraint::Parity(Parity::Odd, cell(a)?),
+            [a, "<", b] => less_than([cell(a)?, cell(b)?], layout)?,
+            [a, ">", b] => less_than([cell(b)?, cell(a)?], layout)?,
+            ["white", a, b] => kropki(KropkiDot::White, [cell(a)?, cell(b)?], layout)?,
+            ["black", a, b] => kropki(KropkiDot::Black, [cell(a)?, cell(b)?], layout)?,
+            ["thermo", rest @ ..] if rest.len() > 1 => {
+                thermometer(cells(rest)?, layout)?
+            },
+            ["arrow", circle, rest @ ..] if !rest.is_empty() => {
+                Constraint::Arrow { circle: cell(circle)?, arrow: cells(rest)? }
+            },
+            _ => return None,
+        };
+
+        constraints.push(c);
+    }
+
+    Some(constraints)
+}
+
+// The inequality, None if the cells aren't next to each other
+fn less_than(cells: [CellIndex; 2], layout: &Layout) -> Option<Constraint> {
+    next_to(cells[0], cells[1], layout).then_some(Constraint::LessThan(cells))
+}
+
+// The dot, None if the cells aren't next to each other
+fn kropki(dot: KropkiDot, cells: [CellIndex; 2], layout: &Layout) -> Option<Constraint> {
+    next_to(cells[0], cells[1], layout).then_some(Constraint::Kropki(dot, cells))
+}
+
+// The thermometer, None if a cell repeats or doesn't touch the one
+// before it
+fn thermometer(cells: Vec<CellIndex>, layout: &Layout) -> Option<Constraint> {
+    let path = cells.windows(2).all(|w| {
+        let (ra, ca) = layout.position_of(w[0]);
+        let (rb, cb) = layout.position_of(w[1]);
+
+        ra.abs_diff(rb) <= 1 && ca.abs_diff(cb) <= 1
+    });
+
+    let repeats = cells.iter().enumerate().any(|(i, c)| cells[..i].contains(c));
+
+    (path && !repeats).then_some(Constraint::Thermometer(cells))
+}
+
+// If the cells share a side
+fn next_to(a: CellIndex, b: CellIndex, layout: &Layout) -> bool {
+    let (ra, ca) = layout.position_of(a);
+    let (rb, cb) = layout.position_of(b);
+
+    ra.abs_diff(rb) + ca.abs_diff(cb) == 1
+}
+
+fn parse_cell(w: &str, layout: &Layout) -> Option<CellIndex> {
+    let (ri, coi) = w.strip_prefix('r')?.split_once('c')?;
+
+    let ri: usize  = ri.parse().ok()?;
+    let coi: usize = coi.parse().ok()?;
+
+    layout.cell_at(ri.checked_sub(1)?, coi.checked_sub(1)?)
+}
@@ -14,6 +14,48 @@ pub enum EntryType {
     BoxLineReduction,
     NakedGroup,
     HiddenGroup,
+    CageCombination,
+    InnieOutie,
+    Thermometer,
+    Arrow,
+    Kropki,
+    Parity,
+    Inequality,
+}
+
+impl EntryType {
+    // Every entry type, in the order they are declared
+    pub const ALL: [EntryType; 15] = {
+        use EntryType::*;
+        [RsCell, CellSolved, NakedSingle, HiddenSingle, PointedGroup,
+         BoxLineReduction, NakedGroup, HiddenGroup, CageCombination,
+         InnieOutie, Thermometer, Arrow, Kropki, Parity, Inequality]
+    };
+
+    // How much a step of this type adds to the difficulty of a puzzle,
+    // roughly how hard it is to spot by hand. Guesses and the
+    // bookkeeping of solved cells don't count.
+    pub const fn weight(self) -> usize {
+        use EntryType::*;
+
+        match self {
+            RsCell           => 0,
+            CellSolved       => 0,
+            NakedSingle      => 1,
+            HiddenSingle     => 2,
+            Parity           => 2,
+            Inequality       => 3,
+            Thermometer      => 3,
+            Kropki           => 3,
+            Arrow            => 4,
+            CageCombination  => 4,
+            PointedGroup     => 5,
+            BoxLineReduction => 5,
+            InnieOutie       => 6,
+            NakedGroup       => 8,
+            HiddenGroup      => 10,
+        }
+    }
 }
 
 
@@ -32,12 +74,41 @@ pub struct CellChange {
 }
 
 
-impl fmt::Display for HistoryEntry {
+// How many entries of each type the history has, in the order of
+// EntryType::ALL
+pub fn count_entry_types(history: &[HistoryEntry]) -> [usize; EntryType::ALL.len()] {
+    let mut counts = [0; EntryType::ALL.len()];
+
+    for h in history {
+        counts[h.name as usize] += 1;
+    }
+
+    counts
+}
+
+
+// The entry doesn't know the size of its board, which is needed to
+// show one bit per digit, so it is displayed through EntryDisplay.
+impl HistoryEntry {
+    pub fn display(&self, size: usize) -> EntryDisplay<'_> {
+        EntryDisplay { entry: self, size }
+    }
+}
+
+pub struct EntryDisplay<'a> {
+    entry: &'a HistoryEntry,
+    size: usize,
+}
+
+impl fmt::Display for EntryDisplay<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "HistoryEntry: {:?}",  self.name)?;
-        write!(f,   "    cells: {:?}",     self.cells)?;
-        writeln!(f, "    digits: {:0>9b}", self.digits.get_digits())?;
-        write!(f,   "    changes: [{}]",   self.changes.iter().format(", "))
+        let h = self.entry;
+        let n = self.size;
+
+        writeln!(f, "HistoryEntry: {:?}",    h.name)?;
+        write!(f,   "    cells: {:?}",       h.cells)?;
+        writeln!(f, "    digits: {:0>n$b}",  h.digits.get_digits())?;
+        write!(f,   "    changes: [{}]",     h.changes.iter().format(", "))
     }
 }
 
@@ -0,0 +1,722 @@
+use std::ops::{Range, RangeInclusive};
+use std::sync::{Arc, OnceLock};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::cell::MAX_DIGIT;
+use crate::random;
+use crate::index_manip::*;
+use crate::constraint::Constraint;
+
+// The sections (aka houses) of a sudoku board. Each section is a set of
+// cells that must contain each digit exactly once, so every section has
+// as many cells as the board has digits (its size).
+//
+// The standard layout is made of the 9 rows, 9 cols and 9 boxes, in
+// that order. Variants can add more sections on top, and every rule
+// that works with sections will pick them up.
+//
+// Boards don't have to be 9x9, any size that can be split into boxes
+// works (4x4, 6x6, 16x16, 25x25...). Cells are always numbered left to
+// right, top to bottom.
+//
+// Boards made of several overlapping grids (like samurai) are drawn on
+// a bigger grid with gaps in it, where each position may or may not
+// have a cell. Cells are still numbered left to right, top to bottom,
+// skipping the gaps.
+//
+// Variants:
+//     x_sudoku: both main diagonals must also contain every digit
+//     jigsaw:   the boxes are replaced by irregular regions
+//     killer:   cages of cells whose digits must add up to a given sum,
+//               without repeating a digit within the cage
+//     samurai:  five 9x9 grids, the corner boxes of the middle one are
+//               shared with the grids on each of its corners
+//     constraints: markings like thermometers, arrows and kropki dots,
+//               see constraint.rs
+//     anti-knight/anti-king:
+//               cells a chess knight/king move apart can't have the
+//               same digit, these only add peers and no sections
+
+
+pub type Section = Vec<CellIndex>;
+
+#[derive(Debug, Clone)]
+pub struct Layout {
+    size: usize,
+    box_rows: usize,
+    box_cols: usize,
+    width: usize,
+    positions: Vec<usize>,
+    cell_at: Vec<Option<CellIndex>>,
+    sections: Vec<Section>,
+    section_types: Vec<SectionType>,
+    sections_of: Vec<Vec<SecIndex>>,
+    peers: Vec<Vec<CellIndex>>,
+    intersections: Vec<Intersection>,
+    regions: Vec<usize>,
+    cages: Vec<Cage>,
+    cage_of: Vec<Option<usize>>,
+    constraints: Vec<Constraint>,
+    constraints_of: Vec<Vec<usize>>,
+}
+
+/* Structure:
+    size:
+        the amount of digits, which is also the length of a row/col.
+
+    box_rows, box_cols:
+        the shape of a box, box_rows * box_cols == size.
+        Boxes are wider than they are tall (6x6 boxes are 2x3).
+
+    width:
+        the amount of columns of the grid the board is drawn on,
+        the same as size unless the board is made of several grids.
+
+    positions:
+        per cell, its position on the grid (row * width + col).
+
+    cell_at:
+        per position on the grid, the cell there, if any.
+
+    sections:
+        the cells of each section.
+
+    section_types:
+        what each section is, used to display them.
+
+    sections_of:
+        per cell, the index of each section the cell belongs to.
+
+    peers:
+        per cell, every other cell that shares a section with it.
+        These are the cells that can't have the same digit.
+
+    intersections:
+        every pair of sections that share more than one cell.
+
+    regions:
+        the box (or jigsaw region) of each cell, only used to know
+        where to draw the thick borders when displaying the board.
+
+    cages:
+        the killer cages of the board, empty unless the sudoku is a
+        killer sudoku. The cells of a cage are also each other's peers.
+
+    cage_of:
+        per cell, the index of the cage it belongs to, if any.
+        Cages never overlap.
+
+    constraints:
+        the markings drawn on the board, if any.
+
+    constraints_of:
+        per cell, the index of each constraint the cell is part of.
+*/
+
+#[derive(Debug, Clone)]
+pub struct Intersection {
+    pub sections: [SecIndex; 2],
+    pub cells: Vec<CellIndex>,
+    pub rest: [Vec<CellIndex>; 2],
+}
+
+/* Structure:
+    sections:
+        the two sections that intersect.
+
+    cells:
+        the cells that are in both sections.
+
+    rest:
+        per section, the cells of the section that aren't in cells.
+*/
+
+#[derive(Debug, Clone)]
+pub struct Cage {
+    pub cells: Vec<CellIndex>,
+    pub sum: usize,
+}
+
+static STANDARD: OnceLock<Arc<Layout>> = OnceLock::new();
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl Layout {
+    // A layout with no sections, but with the regions set to the boxes
+    pub fn empty(box_rows: usize, box_cols: usize) -> Self {
+        let size = box_rows * box_cols;
+
+        Self::empty_grid(box_rows, box_cols, size, vec![true; size * size])
+    }
+
+    // A layout with no sections on a grid of the given width, with a cell
+    // at every position where has_cell is true. The regions are set to
+    // the boxes of the grid, numbered left to right, top to bottom.
+    fn empty_grid(box_rows: usize, box_cols: usize,
+                  width: usize, has_cell: Vec<bool>) -> Self {
+        let size = box_rows * box_cols;
+
+        debug_assert!(size <= MAX_DIGIT, "{size}x{size} boards are too big");
+
+        let boxes_per_row = width.div_ceil(box_cols);
+
+        let mut positions = Vec::with_capacity(has_cell.len());
+        let mut cell_at   = vec![None; has_cell.len()];
+        let mut regions   = Vec::with_capacity(has_cell.len());
+
+        for (pos, has) in has_cell.into_iter().enumerate() {
+            if !has {
+                continue;
+            }
+
+            let (ri, coi) = (pos / width, pos % width);
+
+            cell_at[pos] = Some(positions.len());
+            positions.push(pos);
+            regions.push((ri / box_rows) * boxes_per_row + coi / box_cols);
+        }
+
+        let cell_count = positions.len();
+
+        Self {
+            size,
+            box_rows,
+            box_cols,
+            width,
+            positions,
+            cell_at,
+            sections: Vec::with_capacity(3 * size),
+            section_types: Vec::with_capacity(3 * size),
+            sections_of: vec![Vec::new(); cell_count],
+            peers: vec![Vec::new(); cell_count],
+            intersections: Vec::with_capacity(6 * size),
+            regions,
+            cages: Vec::new(),
+            cage_of: vec![None; cell_count],
+            constraints: Vec::new(),
+            constraints_of: vec![Vec::new(); cell_count],
+        }
+    }
+
+    pub fn standard() -> Self {
+        Self::with_box_size(3, 3)
+    }
+
+    // Building a layout isn't free, so boards with the standard
+    // layout all share the same one.
+    pub fn shared_standard() -> Arc<Self> {
+        Arc::clone(STANDARD.get_or_init(|| Arc::new(Self::standard())))
+    }
+
+    // Rows, then cols, then boxes of a board with size
+    // box_rows * box_cols.
+    pub fn with_box_size(box_rows: usize, box_cols: usize) -> Self {
+        let mut l = Self::empty(box_rows, box_cols);
+
+        l.add_lines();
+
+        l.add_boxes();
+
+        l
+    }
+
+    // The rows and cols of each grid are their own sections, but the
+    // boxes that are shared between grids are only added once. Rows and
+    // cols are numbered by their position on the whole board, so the
+    // top two grids both have a Row 0.
+    pub fn samurai() -> Self {
+        const GRIDS: [(usize, usize); 5] = [
+            (0, 0), (0, 12), (6, 6), (12, 0), (12, 12),
+        ];
+        const WIDTH: usize = 21;
+
+        let in_grid = |pos: usize| {
+            let (ri, coi) = (pos / WIDTH, pos % WIDTH);
+
+            GRIDS.iter().any(|&(gr, gc)| {
+                (gr..gr + 9).contains(&ri) && (gc..gc + 9).contains(&coi)
+            })
+        };
+
+        let mut l = Self::empty_grid(3, 3, WIDTH,
+                                     (0..WIDTH * WIDTH).map(in_grid).collect());
+
+        let cell = |l: &Self, ri, coi| l.cell_at(ri, coi).expect("in grid");
+
+        for (gr, gc) in GRIDS {
+            for i in 0..9 {
+                let row = (0..9).map(|j| cell(&l, gr + i, gc + j)).collect();
+
+                l.add_section(SectionType::Row(gr + i), row);
+            }
+        }
+        for (gr, gc) in GRIDS {
+            for i in 0..9 {
+                let col = (0..9).map(|j| cell(&l, gr + j, gc + i)).collect();
+
+                l.add_section(SectionType::Col(gc + i), col);
+            }
+        }
+
+        l.add_boxes();
+
+        l
+    }
+
+    // Adds every region as a box, in order of the regions
+    fn add_boxes(&mut self) {
+        let mut boxes: Vec<Vec<CellIndex>> = Vec::new();
+
+        for ci in 0..self.cell_count() {
+            let r = self.regions[ci];
+
+            if boxes.len() <= r {
+                boxes.resize(r + 1, Vec::new());
+            }
+
+            boxes[r].push(ci);
+        }
+
+        for (i, b) in boxes.into_iter().enumerate() {
+            if !b.is_empty() {
+                self.add_section(SectionType::Box(i), b);
+            }
+        }
+    }
+
+    // Picks the squarest box shape that fits the size, None if there
+    // isn't one (or the size is too big).
+    pub fn of_size(size: usize) -> Option<Self> {
+        let (box_rows, box_cols) = box_shape_of(size)?;
+
+        Some(Self::with_box_size(box_rows, box_cols))
+    }
+
+    pub fn x_sudoku() -> Self {
+        let mut l = Self::standard();
+
+        l.add_diagonals();
+
+        l
+    }
+
+    // Each element of regions is the region (0 to size-1) of the cell at
+    // that index. Every region must have exactly size cells.
+    pub fn jigsaw(regions: &[usize]) -> Option<Self> {
+        let size = size_of_board(regions.len())?;
+
+        if size > MAX_DIGIT {
+            return None;
+        }
+
+        // The boxes aren't sections of a jigsaw, they only decide how the
+        // digits of a cell are laid out in Debug. Sizes that can't be
+        // split into boxes (like 5x5 and 7x7) get a single row of digits.
+        let (box_rows, box_cols) = box_shape_of(size).unwrap_or((1, size));
+
+        let mut region_cells = vec![Vec::with_capacity(size); size];
+
+        for (ci, &r) in regions.iter().enumerate() {
+            if r >= size {
+                return None;
+            }
+
+            region_cells[r].push(ci);
+        }
+
+        let mut l = Self::empty(box_rows, box_cols);
+
+        l.add_lines();
+
+        for (i, cells) in region_cells.into_iter().enumerate() {
+            if cells.len() != size {
+                return None;
+            }
+
+            l.add_section(SectionType::Region(i), cells);
+        }
+
+        l.regions = regions.to_vec();
+
+        Some(l)
+    }
+
+    // Reads the cells left to right, top to bottom, where each character
+    // is the label of the region the cell belongs to. Any characters can
+    // be used as labels, as long as there's one per region.
+    // Whitespace is ignored.
+    //
+    //     111222333
+    //     111222333
+    //     ...
+    pub fn from_region_str(s: &str) -> Option<Self> {
+        let mut labels = Vec::with_capacity(9);
+        let mut regions = Vec::with_capacity(81);
+
+        for ch in s.chars() {
+            if ch.is_whitespace() {
+                continue;
+            }
+
+            regions.push(match labels.iter().position(|&l| l == ch) {
+                Some(r) => r,
+                None => {
+                    labels.push(ch);
+                    labels.len() - 1
+                },
+            });
+        }
+
+        Self::jigsaw(&regions)
+    }
+
+    fn add_lines(&mut self) {
+        let n = self.size;
+
+        for i in 0..n {
+            self.add_section(SectionType::Row(i), (0..n).map(|j| i*n + j).collect());
+        }
+        for i in 0..n {
+            self.add_section(SectionType::Col(i), (0..n).map(|j| j*n + i).collect());
+        }
+    }
+
+    // Diagonal 0 goes from the top left to the bottom right,
+    // diagonal 1 from the top right to the bottom left.
+    pub fn add_diagonals(&mut self) {
+        let n = self.size;
+
+        self.add_section(SectionType::Diagonal(0),
+                         (0..n).map(|i| i * (n + 1)).collect());
+        self.add_section(SectionType::Diagonal(1),
+                         (0..n).map(|i| (i + 1) * (n - 1)).collect());
+    }
+
+    pub fn add_section(&mut self, t: SectionType, section: Section) {
+        debug_assert!(section.len() == self.size,
+            "A section must have {} cells", self.size);
+
+        let si = self.sections.len();
+
+        for (osi, other) in self.sections.iter().enumerate() {
+            let mut cells = Vec::new();
+
+            for &ci in &section {
+                if other.contains(&ci) {
+                    cells.push(ci);
+                }
+            }
+
+            if cells.len() > 1 {
+                let rest_of = |s: &Section| -> Vec<CellIndex> {
+                    s.iter().filter(|c| !cells.contains(c)).copied().collect()
+                };
+
+                self.intersections.push(Intersection {
+                    sections: [osi, si],
+                    rest: [rest_of(other), rest_of(&section)],
+                    cells,
+                });
+            }
+        }
+
+        for &ci in &section {
+            debug_assert!(ci < self.cell_count(), "Invalid cell index {ci}");
+
+            self.sections_of[ci].push(si);
+        }
+
+        self.link_peers(&section);
+
+        self.sections.push(section);
+        self.section_types.push(t);
+    }
+
+    // Makes every cell in cells a peer of the others
+    fn link_peers(&mut self, cells: &[CellIndex]) {
+        for &ci in cells {
+            for &p in cells {
+                if p != ci && !self.peers[ci].contains(&p) {
+                    self.peers[ci].push(p);
+                }
+            }
+        }
+    }
+
+    // No two cells a chess knight's move apart can have the same digit
+    pub fn add_anti_knight(&mut self) {
+        self.link_moves(&[(1, -2), (1, 2), (2, -1), (2, 1)]);
+    }
+
+    // No two cells a chess king's move apart can have the same digit
+    pub fn add_anti_king(&mut self) {
+        self.link_moves(&[(0, 1), (1, -1), (1, 0), (1, 1)]);
+    }
+
+    // Links every cell with the cells that are the given (row, col)
+    // offsets away from it. Only one direction of each move is needed.
+    fn link_moves(&mut self, moves: &[(isize, isize)]) {
+        for ci in 0..self.cell_count() {
+            let (ri, coi) = self.position_of(ci);
+
+            for &(dr, dc) in moves {
+                let nr = ri.checked_add_signed(dr);
+                let nc = coi.checked_add_signed(dc);
+
+                if let Some(nb) = nr.zip(nc).and_then(|(r, c)| self.cell_at(r, c)) {
+                    self.link_peers(&[ci, nb]);
+                }
+            }
+        }
+    }
+
+    pub fn add_cage(&mut self, cells: Vec<CellIndex>, sum: usize) {
+        let ki = self.cages.len();
+
+        for &ci in &cells {
+            debug_assert!(self.cage_of[ci].is_none(),
+                "Cell {ci} is already in a cage");
+
+            self.cage_of[ci] = Some(ki);
+        }
+
+        self.link_peers(&cells);
+
+        self.cages.push(Cage { cells, sum });
+    }
+
+    pub fn add_constraint(&mut self, c: Constraint) {
+        let ki = self.constraints.len();
+
+        let cells = c.cells();
+
+        for &ci in &cells {
+            debug_assert!(ci < self.cell_count(), "Invalid cell index {ci}");
+
+            self.constraints_of[ci].push(ki);
+        }
+
+        if c.is_distinct() {
+            self.link_peers(&cells);
+        }
+
+        self.constraints.push(c);
+    }
+
+    // Splits a solved board into random cages of orthogonally connected
+    // cells, with the sums taken from the solution. Cages have at most
+    // max_cage_size cells and never repeat a digit. Panics if
+    // max_cage_size is below 2, since a single cell cage is just a given.
+    //
+    // Cages are grown from a random cell one neighbour at a time, so
+    // some may end up smaller than intended when they get boxed in.
+    pub fn add_random_cages(&mut self, solution: &[usize],
+                                       max_cage_size: usize) {
+        debug_assert!(solution.len() == self.cell_count(),
+            "Expected {} cells", self.cell_count());
+        assert!(max_cage_size >= 2, "Cages need at least 2 cells");
+
+        let mut r = random::rng();
+
+        let mut order = Vec::from_iter(0..self.cell_count());
+        order.shuffle(&mut r);
+
+        for start in order {
+            if self.cage_of[start].is_some() {
+                continue;
+            }
+
+            let target = r.gen_range(2..=max_cage_size);
+            let mut cells = vec![start];
+
+            while cells.len() < target {
+                let mut options = Vec::new();
+
+                for &ci in &cells {
+                    let (ri, coi) = self.position_of(ci);
+
+                    let neighbours = [
+                        ri.checked_sub(1).and_then(|r| self.cell_at(r, coi)),
+                        self.cell_at(ri + 1, coi),
+                        coi.checked_sub(1).and_then(|c| self.cell_at(ri, c)),
+                        self.cell_at(ri, coi + 1),
+                    ];
+
+                    for nb in neighbours.into_iter().flatten() {
+                        if self.cage_of[nb].is_none()
+                                && !cells.contains(&nb)
+                                && !options.contains(&nb)
+                                && cells.iter().all(|&c| solution[c] != solution[nb]) {
+                            options.push(nb);
+                        }
+                    }
+                }
+
+                let Some(&nb) = options.choose(&mut r) else {
+                    break;
+                };
+
+                cells.push(nb);
+            }
+
+            let sum = cells.iter().map(|&c| solution[c]).sum();
+
+            self.add_cage(cells, sum);
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn digit_range(&self) -> RangeInclusive<usize> {
+        1..=self.size
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.cell_at.len() / self.width
+    }
+
+    // The (row, col) of the cell on the grid the board is drawn on
+    pub fn position_of(&self, ci: CellIndex) -> (usize, usize) {
+        (self.positions[ci] / self.width, self.positions[ci] % self.width)
+    }
+
+    // None if there's no cell at that position, or if it's off the grid
+    pub fn cell_at(&self, ri: usize, coi: usize) -> Option<CellIndex> {
+        if coi >= self.width {
+            return None;
+        }
+
+        self.cell_at.get(ri * self.width + coi).copied().flatten()
+    }
+
+    pub fn box_shape(&self) -> (usize, usize) {
+        (self.box_rows, self.box_cols)
+    }
+
+    // If the board is a single grid with nothing but its rows, cols and
+    // boxes, the same as the layouts made by with_box_size.
+    pub fn is_plain(&self) -> bool {
+        let n = self.size;
+
+        let standard_peers = 3 * n - self.box_rows - self.box_cols - 1;
+
+        self.width == n
+            && self.cell_count() == n * n
+            && self.section_count() == 3 * n
+            && self.cages.is_empty()
+            && self.constraints.is_empty()
+            && self.section_types.iter().all(|st| {
+                matches!(st, SectionType::Row(_) | SectionType::Col(_)
+                                                 | SectionType::Box(_))
+            })
+            && self.peers.iter().all(|p| p.len() == standard_peers)
+    }
+
+    pub fn section_count(&self) -> usize {
+        self.sections.len()
+    }
+
+    pub fn section_range(&self) -> Range<SecIndex> {
+        0..self.sections.len()
+    }
+
+    pub fn section(&self, si: SecIndex) -> &[CellIndex] {
+        &self.sections[si]
+    }
+
+    pub fn section_type(&self, si: SecIndex) -> SectionType {
+        self.section_types[si]
+    }
+
+    pub fn sections_of(&self, ci: CellIndex) -> &[SecIndex] {
+        &self.sections_of[ci]
+    }
+
+    pub fn peers_of(&self, ci: CellIndex) -> &[CellIndex] {
+        &self.peers[ci]
+    }
+
+    pub fn intersections(&self) -> &[Intersection] {
+        &self.intersections
+    }
+
+    pub fn region_of(&self, ci: CellIndex) -> usize {
+        self.regions[ci]
+    }
+
+    pub fn cages(&self) -> &[Cage] {
+        &self.cages
+    }
+
+    pub fn has_cages(&self) -> bool {
+        !self.cages.is_empty()
+    }
+
+    pub fn cage_of(&self, ci: CellIndex) -> Option<usize> {
+        self.cage_of[ci]
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    pub fn constraints_of(&self, ci: CellIndex) -> &[usize] {
+        &self.constraints_of[ci]
+    }
+}
+
+
+// The length of a side of a square board with cell_count cells
+pub fn size_of_board(cell_count: usize) -> Option<usize> {
+    let size = (cell_count as f64).sqrt().round() as usize;
+
+    if size * size == cell_count {
+        Some(size)
+    }
+    else {
+        None
+    }
+}
+
+// If one of the cells before cells[i] that sees it has digit d, where
+// digits holds the digits of the cells before i. Used by the searches
+// that fill a cage or constraint one cell at a time.
+pub fn repeats_digit(layout: &Layout, cells: &[CellIndex],
+                     digits: &[usize], i: usize, d: usize) -> bool {
+    let peers = layout.peers_of(cells[i]);
+
+    (0..i).any(|j| digits[j] == d && peers.contains(&cells[j]))
+}
+
+fn box_shape_of(size: usize) -> Option<(usize, usize)> {
+    if size > MAX_DIGIT {
+        return None;
+    }
+
+    let mut box_rows = (size as f64).sqrt() as usize;
+
+    while box_rows > 1 && !size.is_multiple_of(box_rows) {
+        box_rows -= 1;
+    }
+
+    if box_rows <= 1 && size != 1 {
+        return None;
+    }
+
+    Some((box_rows, size / box_rows))
+}
@@ -1,10 +1,25 @@
 pub mod cell;
-mod index_manip;
+pub mod index_manip;
+pub mod layout;
 pub mod history;
+pub mod constraint;
+pub mod random;
 pub mod sudoku;
+pub mod batch;
+pub mod stats;
 
 use crate::sudoku::Sudoku;
 
+// A puzzle that takes most of the rules to get as far as they can
+const BENCH_PUZZLE: &str =
+    "52...6.........7.13...........4..8..6......5...........418.........3..2...87.....";
+
 pub fn bench_main() {
     let _sud = Sudoku::fill_incremental();
 }
+
+pub fn bench_solve() {
+    let mut sud = Sudoku::from_puzzle_str(BENCH_PUZZLE).expect("valid puzzle");
+
+    sud.solve();
+}
@@ -1,13 +1,72 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufWriter;
 use std::time::Instant;
 
-use simple_sudoku_generator::sudoku::Sudoku;
+use simple_sudoku_generator::sudoku::{Sudoku, DifferentialError, Symmetry, Transform};
+use simple_sudoku_generator::layout::Layout;
+use simple_sudoku_generator::constraint::parse_constraints;
 use simple_sudoku_generator::history::EntryType;
+use simple_sudoku_generator::batch::{self, BatchConfig, CatalogFormat};
+use simple_sudoku_generator::stats::GenerationStats;
 
 // Program modifiers
 const AMOUNT_RUNS:   u32            = 5000;
 const RUN_FUNC:      fn()           = run_once;
 const CREATE_SUDOKU: fn() -> Sudoku = Sudoku::fill_incremental;
 const PRINT_HISTORY: bool           = false;
+const MAX_CHANGES:   usize          = 6;
+const KILLER_CAGE_SIZE: usize       = 4;
+const SYMMETRY:      Symmetry       = Symmetry::Rotational;
+const MINIMIZE_ATTEMPTS: usize      = 20;
+
+// Used by run_batch, 0 threads is one per core
+const BATCH_FILE:    &str           = "puzzles.txt";
+const BATCH_THREADS: usize          = 0;
+
+// Used by run_catalog
+const CATALOG_FILE:   &str          = "catalog.jsonl";
+const CATALOG_FORMAT: CatalogFormat = CatalogFormat::JsonLines;
+
+// Size of the board used by fill_sized_sudoku (4, 6, 9, 16 or 25)
+const BOARD_SIZE: usize = 16;
+
+// Regions used by fill_jigsaw_sudoku
+const JIGSAW_REGIONS: &str = "\
+    111222333\
+    111222333\
+    111225336\
+    444255366\
+    444555666\
+    444555696\
+    778888996\
+    777889999\
+    777788899";
+
+// Digits kept in place by fill_givens_sudoku, '.' cells are filled in
+const GIVENS: &str = "\
+    123456789\
+    .........\
+    .........\
+    .5.......\
+    .....5...\
+    ......5..\
+    .........\
+    .........\
+    ........1";
+
+// Even/odd and inequality puzzle used by run_constraints
+const CONSTRAINT_PUZZLE: &str =
+    "....76..........7...54........31.6.....564....4.....3..2.1....6.....9........3..2";
+const CONSTRAINTS: &str = "
+    r1c3 > r1c4; r1c6 < r2c6; r1c8 < r2c8; r2c2 > r2c3; r2c2 < r3c2
+    r2c6 > r2c7; r2c8 > r2c9; r2c9 < r3c9; r3c7 < r4c7; r3c8 < r3c9
+    r5c3 < r5c4; r5c7 > r5c8; r5c8 < r5c9; r6c3 < r7c3; r6c9 > r7c9
+    r7c2 < r8c2; r7c4 < r8c4; r7c6 > r7c7; r7c8 > r7c9; r8c1 > r9c1
+    r8c6 > r9c6; r8c7 > r9c7; r8c8 < r8c9; r9c4 > r9c5; r9c8 > r9c9
+    even r2c2; even r2c3; even r2c7; even r3c4; even r4c8; even r5c5
+    even r6c3; even r9c9
+    odd r3c5;  odd r3c6;  odd r7c6;  odd r8c6;  odd r8c9";
 
 // Controls which history entries will be displayed
 #[allow(non_snake_case)]
@@ -21,10 +80,75 @@ const fn DISPLAY_ENTRY_TYPE(he: EntryType) -> bool {
         BoxLineReduction => true,
         NakedGroup       => true,
         HiddenGroup      => true,
+        CageCombination  => true,
+        InnieOutie       => true,
+        Thermometer      => true,
+        Arrow            => true,
+        Kropki           => true,
+        Parity           => true,
+        Inequality       => true,
     }
 }
 
 
+#[allow(dead_code)]
+fn fill_x_sudoku() -> Sudoku {
+    Sudoku::fill_incremental_with(Layout::x_sudoku())
+}
+
+#[allow(dead_code)]
+fn fill_samurai_sudoku() -> Sudoku {
+    Sudoku::fill_incremental_with(Layout::samurai())
+}
+
+#[allow(dead_code)]
+fn fill_anti_knight_sudoku() -> Sudoku {
+    let mut layout = Layout::standard();
+
+    layout.add_anti_knight();
+
+    Sudoku::fill_incremental_with(layout)
+}
+
+#[allow(dead_code)]
+fn fill_anti_king_sudoku() -> Sudoku {
+    let mut layout = Layout::standard();
+
+    layout.add_anti_king();
+
+    Sudoku::fill_incremental_with(layout)
+}
+
+#[allow(dead_code)]
+fn fill_sized_sudoku() -> Sudoku {
+    let layout = Layout::of_size(BOARD_SIZE).expect("invalid board size");
+
+    Sudoku::fill_incremental_with(layout)
+}
+
+#[allow(dead_code)]
+fn fill_sampled_sudoku() -> Sudoku {
+    let layout = Layout::of_size(BOARD_SIZE).expect("invalid board size");
+
+    Sudoku::fill_sampled_with(layout).expect("the layout has no valid board")
+}
+
+#[allow(dead_code)]
+fn fill_jigsaw_sudoku() -> Sudoku {
+    let layout = Layout::from_region_str(JIGSAW_REGIONS).expect("invalid regions");
+
+    Sudoku::fill_incremental_with(layout)
+}
+
+#[allow(dead_code)]
+fn fill_givens_sudoku() -> Sudoku {
+    let givens = Sudoku::from_puzzle_str(GIVENS).expect("invalid givens");
+
+    Sudoku::fill_from_givens(&givens.to_solutions())
+        .expect("givens can't be completed")
+}
+
+
 fn main() {
     let time = Instant::now();
 
@@ -46,20 +170,23 @@ fn run_once() {
 
 #[allow(dead_code)]
 fn run_amount() {
-    let mut failure_count = 0;
+    let mut stats = GenerationStats::new();
 
     for _ in 0..AMOUNT_RUNS {
+        let time = Instant::now();
         let sud = CREATE_SUDOKU();
 
+        stats.add(&sud, time.elapsed());
+
         print_history(&sud);
 
         if !sud.is_valid() {
             sud.print_invalid_cells();
-            failure_count += 1;
+            println!("{}", sud.get_incorrectness(MAX_CHANGES));
         }
     }
 
-    println!("Failure Count: {failure_count}");
+    println!("{stats}");
 }
 
 #[allow(dead_code)]
@@ -74,6 +201,7 @@ fn run_until_failure() {
             print_history(&sud);
             println!("{sud}");
             sud.print_invalid_cells();
+            sud.print_contradiction();
             have_failure = true;
             break;
         }
@@ -90,22 +218,261 @@ fn run_until_failure() {
     }
 }
 
+// Solves killer sudokus with no givens, using only their cages
+#[allow(dead_code)]
+fn run_killer() {
+    let (mut solved, mut unsolved, mut failure_count) = (0, 0, 0);
+    let mut unique = 0;
+
+    for _ in 0..AMOUNT_RUNS {
+        let Some(sud) = Sudoku::fill_killer(KILLER_CAGE_SIZE) else {
+            continue;
+        };
+
+        let puzzle = Sudoku::with_layout(sud.shared_layout());
+
+        if puzzle.has_unique_solution() {
+            unique += 1;
+        }
+
+        match puzzle.differential_check() {
+            Ok(_) => solved += 1,
+            Err(DifferentialError::Unsolved) => unsolved += 1,
+            Err(e) => {
+                println!("{e:?}");
+                puzzle.print_cages();
+                failure_count += 1;
+            },
+        }
+    }
+
+    println!("Solved: {solved}, Unsolved: {unsolved}, Failures: {failure_count}");
+    println!("Unique: {unique}");
+}
+
+// Solves CONSTRAINT_PUZZLE with the markings from CONSTRAINTS
+#[allow(dead_code)]
+fn run_constraints() {
+    let mut layout = Layout::standard();
+
+    let constraints = parse_constraints(CONSTRAINTS, &layout)
+                          .expect("invalid constraints");
+
+    for c in constraints {
+        layout.add_constraint(c);
+    }
+
+    let puzzle = Sudoku::from_puzzle_str_with(layout, CONSTRAINT_PUZZLE)
+                     .expect("invalid constraint puzzle");
+
+    println!("{puzzle}");
+
+    match puzzle.differential_check() {
+        Ok(sud) => {
+            print_history(&sud);
+            println!("{sud}");
+        },
+        Err(e) => println!("{e:?}"),
+    }
+
+    println!("Unique: {}", puzzle.has_unique_solution());
+}
+
+// Makes puzzles with SYMMETRY out of the generated boards
+#[allow(dead_code)]
+fn run_symmetric() {
+    let (mut solved, mut unsolved, mut failure_count) = (0, 0, 0);
+    let mut clues = Vec::new();
+
+    for _ in 0..AMOUNT_RUNS {
+        let sud = CREATE_SUDOKU();
+
+        if !sud.is_valid_solution() {
+            continue;
+        }
+
+        let puzzle = sud.remove_clues(SYMMETRY);
+
+        if clues.is_empty() {
+            println!("{puzzle}");
+        }
+
+        clues.push(puzzle.clue_count());
+
+        match puzzle.differential_check() {
+            Ok(_) => solved += 1,
+            Err(DifferentialError::Unsolved) => unsolved += 1,
+            Err(e) => {
+                println!("{e:?}\nPuzzle:\n{puzzle}");
+                failure_count += 1;
+            },
+        }
+    }
+
+    let average = clues.iter().sum::<usize>() as f64 / clues.len() as f64;
+
+    println!("Solved: {solved}, Unsolved: {unsolved}, Failures: {failure_count}");
+    println!("Average clues: {average:.1}, Fewest: {:?}", clues.iter().min());
+}
+
+// Reduces the generated boards to minimal puzzles, keeping the best of
+// MINIMIZE_ATTEMPTS for each
+#[allow(dead_code)]
+fn run_minimal() {
+    let mut fewest: Option<Sudoku> = None;
+    let mut clue_counts = Vec::new();
+    let mut failure_count = 0;
+
+    for _ in 0..AMOUNT_RUNS {
+        let sud = CREATE_SUDOKU();
+
+        if !sud.is_valid_solution() {
+            continue;
+        }
+
+        let puzzle = sud.minimize(MINIMIZE_ATTEMPTS)
+                        .expect("a filled board has a unique solution");
+
+        if let Err(DifferentialError::NoSolution
+                   | DifferentialError::BadElimination(_)) =
+                puzzle.differential_check() {
+            failure_count += 1;
+        }
+
+        clue_counts.push(puzzle.clue_count());
+
+        if fewest.as_ref().is_none_or(|f| puzzle.clue_count() < f.clue_count()) {
+            fewest = Some(puzzle);
+        }
+    }
+
+    if let Some(f) = fewest {
+        println!("{f}");
+        println!("Clues: {}", f.clue_count());
+    }
+
+    let average = clue_counts.iter().sum::<usize>() as f64
+                      / clue_counts.len() as f64;
+
+    println!("Average clues: {average:.1}, Failures: {failure_count}");
+}
+
+// Generates AMOUNT_RUNS puzzles on several threads into BATCH_FILE
+#[allow(dead_code)]
+fn run_batch() {
+    let mut config = BatchConfig::new(AMOUNT_RUNS as usize);
+
+    config.threads  = BATCH_THREADS;
+    config.symmetry = SYMMETRY;
+
+    let file = File::create(BATCH_FILE).expect("can't create batch file");
+
+    batch::write_batch(&config, &mut BufWriter::new(file))
+        .expect("can't write batch file");
+
+    println!("Wrote {} puzzles to {BATCH_FILE}, first seed: {}",
+             config.count, config.seed);
+}
+
+// Same as run_batch, with the solution, seed and rating of each puzzle
+#[allow(dead_code)]
+fn run_catalog() {
+    let mut config = BatchConfig::new(AMOUNT_RUNS as usize);
+
+    config.threads  = BATCH_THREADS;
+    config.symmetry = SYMMETRY;
+
+    let file = File::create(CATALOG_FILE).expect("can't create catalog file");
+
+    batch::write_catalog(&config, CATALOG_FORMAT, &mut BufWriter::new(file))
+        .expect("can't write catalog file");
+
+    println!("Wrote {} puzzles to {CATALOG_FILE}, first seed: {}",
+             config.count, config.seed);
+}
+
+// Counts the generated boards that aren't a disguised copy of an earlier
+// one (only 2 are left for 4x4 boards)
+#[allow(dead_code)]
+fn run_duplicates() {
+    let mut forms = HashSet::new();
+    let mut valid = 0;
+
+    for _ in 0..AMOUNT_RUNS {
+        let sud = CREATE_SUDOKU();
+
+        if !sud.is_valid_solution() {
+            continue;
+        }
+
+        valid += 1;
+
+        if let Some(form) = sud.canonical_form() {
+            forms.insert(form);
+        }
+    }
+
+    println!("Valid boards: {valid}, Distinct: {}", forms.len());
+}
+
+// Turns one puzzle into a few others with the same solution, moved along.
+// The rules can solve them with other steps, so only the solutions are
+// compared.
+#[allow(dead_code)]
+fn run_transforms() {
+    let sud = CREATE_SUDOKU();
+
+    if !sud.is_valid_solution() {
+        println!("Generated board is invalid");
+        return;
+    }
+
+    let puzzle = sud.remove_clues(SYMMETRY);
+
+    let Ok(solved) = puzzle.differential_check() else {
+        println!("Puzzle can't be solved by the rules");
+        return;
+    };
+
+    println!("{puzzle}\nSteps: {}", solved.history.len());
+
+    let transforms = [
+        Transform::Relabel(vec![9, 8, 7, 6, 5, 4, 3, 2, 1]),
+        Transform::PermuteRows(vec![3, 4, 5, 0, 1, 2, 8, 7, 6]),
+        Transform::Rotate,
+        Transform::MirrorVertical,
+    ];
+
+    for t in &transforms {
+        let p = puzzle.transform(t, false).expect("standard boards can be transformed");
+        let s = solved.transform(t, true).expect("standard boards can be transformed");
+
+        println!("{t:?}:\n{p}");
+
+        let solutions = p.find_solutions(2);
+
+        println!("Unique: {}, same solution: {}", solutions.len() == 1,
+                 solutions.first() == Some(&s.to_solutions()));
+    }
+}
+
 fn print_history(sud: &Sudoku) {
     if !PRINT_HISTORY {
         return;
     }
 
-    let mut new_sud = Sudoku::new();
+    let mut new_sud = Sudoku::from_solutions_with(sud.shared_layout(),
+                                                  &sud.givens());
 
     println!("{new_sud}");
 
     for h in &sud.history {
         for cc in &h.changes {
-            new_sud.cells[cc.id] = cc.new_cell;
+            new_sud.set_cell(cc.id, cc.new_cell);
         }
 
         if DISPLAY_ENTRY_TYPE(h.name) {
-            println!("{h}\n{new_sud:?}");
+            println!("{}\n{new_sud:?}", h.display(sud.size()));
         }
     }
 }
\ No newline at end of file
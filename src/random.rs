@@ -0,0 +1,44 @@
+use std::cell::RefCell;
+use rand::{RngCore, SeedableRng};
+use rand::rngs::StdRng;
+
+// The random numbers used to generate boards. Each thread has its own
+// generator, so workers generating boards in parallel never share one.
+// It starts from a random seed, but can be given a seed so that a board
+// can be generated again from the seed it was made with.
+
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+// Restarts the generator of this thread from seed
+pub fn set_seed(seed: u64) {
+    RNG.with(|r| *r.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+// The generator of this thread, to be used like rand::thread_rng()
+pub fn rng() -> BoardRng {
+    BoardRng
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BoardRng;
+
+impl RngCore for BoardRng {
+    fn next_u32(&mut self) -> u32 {
+        RNG.with(|r| r.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        RNG.with(|r| r.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        RNG.with(|r| r.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        RNG.with(|r| r.borrow_mut().try_fill_bytes(dest))
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::history::{EntryType, count_entry_types};
+use crate::sudoku::Sudoku;
+
+// Collects numbers about many generated boards, to see how changes to
+// the generator affect it. Each board is added along with the time it
+// took to generate, and the report is printed with Display.
+//
+// Boards that aren't a valid solution are counted as failures, but
+// their history still counts towards everything else, since the steps
+// that led to a failure are just as much work for the generator.
+//
+// It also has the chi-square test used to check that generated boards
+// are spread out the way they should be (see sampling.rs).
+
+
+#[derive(Debug, Clone, Default)]
+pub struct GenerationStats {
+    pub failures: usize,
+    pub times: Vec<Duration>,
+    pub history_len: usize,
+    pub entry_types: [usize; EntryType::ALL.len()],
+    pub rs_cell_choices: Vec<usize>,
+}
+
+/* Structure:
+    failures:
+        amount of boards that weren't a valid solution.
+
+    times:
+        the time each board took to generate, in the order they were added.
+        Its length is the amount of boards.
+
+    history_len:
+        the amount of history entries of all boards together.
+
+    entry_types:
+        the amount of history entries of each type of all boards together,
+        in the order of EntryType::ALL.
+
+    rs_cell_choices:
+        per amount of random choices (RsCell entries), how many boards
+        needed exactly that many.
+*/
+
+
+impl GenerationStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, sud: &Sudoku, time: Duration) {
+        if !sud.is_valid_solution() {
+            self.failures += 1;
+        }
+
+        self.times.push(time);
+        self.history_len += sud.history.len();
+
+        let counts = count_entry_types(&sud.history);
+
+        for (total, count) in self.entry_types.iter_mut().zip(counts) {
+            *total += count;
+        }
+
+        let choices = counts[EntryType::RsCell as usize];
+
+        if self.rs_cell_choices.len() <= choices {
+            self.rs_cell_choices.resize(choices + 1, 0);
+        }
+
+        self.rs_cell_choices[choices] += 1;
+    }
+
+    pub fn grid_count(&self) -> usize {
+        self.times.len()
+    }
+
+    // The share of boards that were valid, from 0 to 1
+    pub fn success_rate(&self) -> f64 {
+        if self.grid_count() == 0 {
+            return 0.0;
+        }
+
+        1.0 - self.failures as f64 / self.grid_count() as f64
+    }
+
+    // The time that p percent of the boards took at most (nearest rank),
+    // None if there are no boards.
+    pub fn time_percentile(&self, p: f64) -> Option<Duration> {
+        let mut sorted = self.times.clone();
+        sorted.sort_unstable();
+
+        let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+
+        sorted.get(rank.clamp(1, sorted.len().max(1)) - 1).copied()
+    }
+
+    pub fn average_history_len(&self) -> f64 {
+        self.history_len as f64 / self.grid_count().max(1) as f64
+    }
+}
+
+
+// The chi-square statistic of counts, against every count being equally
+// likely. The higher it is, the less likely that is.
+pub fn chi_square(counts: &[usize]) -> f64 {
+    let total: usize = counts.iter().sum();
+    let expected = total as f64 / counts.len() as f64;
+
+    counts.iter()
+        .map(|&c| (c as f64 - expected).powi(2) / expected)
+        .sum()
+}
+
+// How many standard deviations a chi-square statistic with the given
+// degrees of freedom is above its mean, using the Wilson-Hilferty
+// approximation. Values above 3 are unlikely to be chance, below -3 the
+// counts are suspiciously even.
+pub fn chi_square_z(chi: f64, degrees: usize) -> f64 {
+    let k = degrees as f64;
+    let var = 2.0 / (9.0 * k);
+
+    ((chi / k).cbrt() - (1.0 - var)) / var.sqrt()
+}
+
+
+impl fmt::Display for GenerationStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let grids = self.grid_count();
+
+        writeln!(f, "Generation stats:")?;
+        writeln!(f, "    grids:           {grids}")?;
+        writeln!(f, "    success rate:    {:.2}% ({} failures)",
+                 self.success_rate() * 100.0, self.failures)?;
+
+        write!(f, "    time per grid:  ")?;
+
+        let percentiles = [("p50", 50.0), ("p90", 90.0),
+                           ("p99", 99.0), ("max", 100.0)];
+
+        for (name, p) in percentiles {
+            if let Some(t) = self.time_percentile(p) {
+                write!(f, " {name}: {t:.2?}")?;
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "    history length:  {:.1} on average",
+                 self.average_history_len())?;
+
+        writeln!(f, "    entry types (total, per grid):")?;
+
+        for (t, count) in EntryType::ALL.iter().zip(self.entry_types) {
+            writeln!(f, "        {:<18} {count:>10} {:>10.2}",
+                     format!("{t:?}"), count as f64 / grids.max(1) as f64)?;
+        }
+
+        write!(f, "    RsCell choices per grid (choices, grids):")?;
+
+        let most = self.rs_cell_choices.iter().copied().max().unwrap_or(0);
+
+        for (choices, &count) in self.rs_cell_choices.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+
+            // Bars are scaled so the most common amount is 40 wide
+            let bar = "#".repeat((count * 40).div_ceil(most));
+
+            write!(f, "\n        {choices:>4} {count:>8} {bar}")?;
+        }
+
+        Ok(())
+    }
+}
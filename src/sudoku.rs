@@ -1,48 +1,89 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
 use rand::Rng;
 
-use crate::cell::{Cell, CELL_INIT, DIGIT_RANGE};
-use crate::index_manip::*;
+use crate::cell::{Cell, MAX_DIGIT, char_to_digit, digit_to_char};
+use crate::random;
+use crate::layout::{Layout, size_of_board};
 use crate::history::{HistoryEntry, EntryType, CellChange};
 
+use changes::{ChangeTracker, GroupRule, SectionRule};
+
+// Checking that a set of cages has a unique solution gets slow fast as the
+// cages grow: a single check takes up to a few hundred milliseconds with
+// cages of 5 cells, and up to several seconds with 6. With 4 cells about
+// one set of cages in 7 is unique, with 5 about one in 20.
+const MAX_KILLER_CAGE_SIZE: usize = 5;
+const MAX_KILLER_ATTEMPTS:  usize = 100;
+
 mod cell_solved;
 mod hidden_single;
 mod intersection_removal;
 mod naked_single;
 mod group_removal;
 mod graphics;
-
-
-pub type SudokuBoard = [Cell; 81];
+mod contradiction;
+mod incorrectness;
+mod verify;
+mod exact_solver;
+mod differential;
+mod killer;
+mod constraint_rule;
+mod clue_removal;
+mod canonical;
+mod transform;
+mod rating;
+mod sampling;
+mod changes;
+
+pub use contradiction::{Contradiction, ContradictionKind};
+pub use incorrectness::Incorrectness;
+pub use verify::Conflict;
+pub use exact_solver::ExactSolution;
+pub use differential::{BadElimination, DifferentialError};
+pub use clue_removal::Symmetry;
+pub use transform::Transform;
+pub use rating::Rating;
+
+
+pub type SudokuBoard = Vec<Cell>;
 
 pub struct Sudoku {
-    pub cells: SudokuBoard,
-    solved_cell_checked: [bool; 81],
-    section_digit_sum: [[usize; 10]; 27],
+    cells: SudokuBoard,
+    layout: Arc<Layout>,
+    section_digit_sum: Vec<[usize; MAX_DIGIT + 1]>,
     cell_change_stack: Vec<CellChange>,
+    changes: ChangeTracker,
     pub history: Vec<HistoryEntry>,
 }
 
 /* Structure:
     cells:
         all cells in a sudoku board, index 0 is top left,
-        index 80 is the bottom right.
+        the last index is the bottom right (80 on a 9x9 board).
         Read left to right, top to bottom.
 
-    solved_cell_checked:
-        used by cell_solved() to flag which cells have already been
-        checked by the code.
+    layout:
+        the sections of the board, the standard rows/cols/boxes unless
+        the sudoku is a variant. Boards with the same layout share it.
 
     section_digit_sum:
         sum of each digit in each section
-        the first 9 sections are the rows, then cols, then boxes
+        uses the same order as the sections in layout, which in the
+        standard layout are the rows, then cols, then boxes
         each element will have:
             first entry: sum of entries in the section w/ sum above 1
-            entries 1-9: sum of count per section corresponding to each digit
+            entries 1-size: sum of count per section corresponding to each digit
+            entries past the size of the board are unused
 
     cell_change_stack:
         helper var that is used to keep track of changes made by rules in
         an unobtrusive way
 
+    changes:
+        which parts of the board changed since each rule last looked at
+        them, see changes.rs.
+
     history:
         a complete record of changes made by the solving process
 */
@@ -56,54 +97,225 @@ impl Default for Sudoku {
 
 impl Sudoku {
     pub fn new() -> Self {
+        Self::with_layout(Layout::shared_standard())
+    }
+
+    pub fn with_layout(layout: impl Into<Arc<Layout>>) -> Self {
+        let layout = layout.into();
+
+        let size = layout.size();
+
         Self {
-            cells: [CELL_INIT; 81],
-            solved_cell_checked: [false; 81],
-            section_digit_sum: [[9; 10]; 27],
-            cell_change_stack: Vec::with_capacity(27),
+            cells: vec![Cell::with_digits(size); layout.cell_count()],
+            section_digit_sum: vec![[size; MAX_DIGIT + 1];
+                                    layout.section_count()],
+            changes: ChangeTracker::new(&layout),
+            layout,
+            cell_change_stack: Vec::with_capacity(3 * size),
             history: Vec::with_capacity(1000),
         }
     }
 
-    pub fn from_solutions(sol_arr: [usize; 81]) -> Self {
-        let mut s = Self::new();
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    // The layout without copying it, to build other boards on
+    pub fn shared_layout(&self) -> Arc<Layout> {
+        Arc::clone(&self.layout)
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    // Replaces a cell from outside the rules. The change is tracked the
+    // same way as the changes the rules make, so the next solve() looks
+    // at the cell again.
+    pub fn set_cell(&mut self, ci: usize, cell: Cell) {
+        self.cells[ci] = cell;
+
+        if cell.is_solved() {
+            self.changes.cell_solved(&self.layout, ci);
+        }
+        else {
+            self.changes.cell_changed(&self.layout, ci);
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.layout.size()
+    }
+
+    // Every digit a cell of this board can have
+    pub fn digit_range(&self) -> RangeInclusive<usize> {
+        self.layout.digit_range()
+    }
+
+    // The size of the board is taken from the amount of cells, which
+    // must be a square that can be split into boxes.
+    pub fn from_solutions(sol_arr: &[usize]) -> Self {
+        let layout = size_of_board(sol_arr.len())
+            .and_then(Layout::of_size)
+            .expect("Invalid amount of cells");
+
+        Self::from_solutions_with(layout, sol_arr)
+    }
+
+    pub fn from_solutions_with(layout: impl Into<Arc<Layout>>,
+                               sol_arr: &[usize]) -> Self {
+        let mut s = Self::with_layout(layout);
+
+        debug_assert!(sol_arr.len() == s.cells.len(),
+            "Expected {} cells", s.cells.len());
 
-        for i in 0..81 {
+        for (i, &n) in sol_arr.iter().enumerate() {
             // Unfortunately the sudoku can't tell if the cell should be
             // undeveloped or solved to 0. But the solve() method can be
             // called to remedy that.
-            if sol_arr[i] != 0 {
-                let mut c = CELL_INIT;
-                c.solve_cell(sol_arr[i]);
-                s.cells[i] = c;
+            if n != 0 {
+                s.cells[i].solve_cell(n);
+                s.cells[i].set_given();
             }
         }
 
         s
     }
 
+    // The opposite of from_solutions, unsolved cells become 0.
+    pub fn to_solutions(&self) -> Vec<usize> {
+        self.cells.iter().map(|c| c.get_number()).collect()
+    }
+
+    // The puzzle the board was made from, in the same format as
+    // to_solutions. Cells that were solved afterwards are 0.
+    pub fn givens(&self) -> Vec<usize> {
+        self.cells.iter()
+            .map(|c| if c.is_given() { c.get_number() } else { 0 })
+            .collect()
+    }
+
+    // The opposite of from_puzzle_str, for the givens of the board.
+    // Cells that aren't given are '.', on one line with no whitespace.
+    pub fn to_puzzle_str(&self) -> String {
+        self.givens().into_iter()
+            .map(|d| if d == 0 { '.' } else { digit_to_char(d) })
+            .collect()
+    }
+
+    // Reads the cells left to right, top to bottom. Digits 1-9 and then
+    // A-P (for 10-25) are solved cells, '0' and '.' are empty cells.
+    // Whitespace is ignored.
+    //
+    // The size of the board is taken from the amount of cells, so 16
+    // cells is a 4x4 board, 81 is a 9x9 board, 256 is a 16x16 board...
+    pub fn from_puzzle_str(p: &str) -> Option<Self> {
+        let sol_arr = parse_puzzle_str(p)?;
+
+        let layout = Layout::of_size(size_of_board(sol_arr.len())?)?;
+
+        Self::from_puzzle_digits(layout, &sol_arr)
+    }
+
+    // Same as from_puzzle_str, for boards that aren't a single square
+    // grid (or that are a variant). Gaps between grids are skipped, so
+    // the string only has one character per cell of the layout.
+    pub fn from_puzzle_str_with(layout: impl Into<Arc<Layout>>,
+                                p: &str) -> Option<Self> {
+        Self::from_puzzle_digits(layout.into(), &parse_puzzle_str(p)?)
+    }
+
+    fn from_puzzle_digits(layout: impl Into<Arc<Layout>>,
+                          sol_arr: &[usize]) -> Option<Self> {
+        let layout = layout.into();
+
+        if sol_arr.len() != layout.cell_count()
+                || sol_arr.iter().any(|&d| d > layout.size()) {
+            return None;
+        }
+
+        Some(Self::from_solutions_with(layout, sol_arr))
+    }
+
     pub fn fill_incremental() -> Self {
-        let mut s = Self::new();
+        Self::fill_incremental_with(Layout::shared_standard())
+    }
 
-        for i in 0..81 {
+    pub fn fill_incremental_with(layout: impl Into<Arc<Layout>>) -> Self {
+        let mut s = Self::with_layout(layout);
+
+        s.fill_remaining();
+
+        s
+    }
+
+    // Fills in a board around a few cells that are already decided
+    // (the givens), e.g. a fixed first row or a pattern of digits, using
+    // the same 0-for-empty format as from_solutions.
+    //
+    // Givens that can't be part of any solution give None. Otherwise a
+    // random solution of the givens is picked by the exact solver, and
+    // its digits are placed one cell at a time like fill_incremental
+    // does, so the history shows what the rules worked out in between.
+    // The filled board is always a valid solution.
+    pub fn fill_from_givens(givens: &[usize]) -> Option<Self> {
+        let layout = Layout::of_size(size_of_board(givens.len())?)?;
+
+        Self::fill_from_givens_with(layout, givens)
+    }
+
+    pub fn fill_from_givens_with(layout: impl Into<Arc<Layout>>,
+                                 givens: &[usize]) -> Option<Self> {
+        let mut s = Self::from_puzzle_digits(layout, givens)?;
+
+        let solution = s.random_solution()?;
+
+        s.solve();
+
+        for (i, &d) in solution.iter().enumerate() {
             if s.cells[i].is_solved() {
                 continue;
             }
 
-            s.rs_cell(i);
+            // The rules only remove digits that aren't part of any
+            // solution, so the digit of the solution is still there
+            s.cells[i].solve_cell(d);
+
+            s.add_history_entry_from_solution(EntryType::RsCell, i);
 
             s.solve();
         }
 
-        s
+        s.is_valid_solution().then_some(s)
+    }
+
+    // Lets the rules work out what the solved cells already decide, then
+    // keeps picking a digit for the first unsolved cell until the board
+    // is full.
+    fn fill_remaining(&mut self) {
+        self.solve();
+
+        for i in 0..self.cells.len() {
+            if self.cells[i].is_solved() {
+                continue;
+            }
+
+            self.rs_cell(i);
+
+            self.solve();
+        }
     }
 
     pub fn fill_random() -> Self {
-        let mut s = Self::new();
+        Self::fill_random_with(Layout::shared_standard())
+    }
 
-        let mut cell_pool = Vec::from_iter(0..81);
+    pub fn fill_random_with(layout: impl Into<Arc<Layout>>) -> Self {
+        let mut s = Self::with_layout(layout);
 
-        let mut r = rand::thread_rng();
+        let mut cell_pool = Vec::from_iter(0..s.cells.len());
+
+        let mut r = random::rng();
 
         while !cell_pool.is_empty() {
             let i = r.gen_range(0..cell_pool.len());
@@ -121,6 +333,45 @@ impl Sudoku {
         s
     }
 
+    // A killer sudoku with no givens, the cages are generated from a
+    // filled standard board and the solution is left on the board.
+    // New cages are generated until they only fit the one solution.
+    //
+    // None if max_cage_size is below 2 or above 5, or if no valid board
+    // or no unique set of cages turned up within a limited amount of
+    // attempts.
+    pub fn fill_killer(max_cage_size: usize) -> Option<Self> {
+        if !(2..=MAX_KILLER_CAGE_SIZE).contains(&max_cage_size) {
+            return None;
+        }
+
+        let solution = (0..MAX_KILLER_ATTEMPTS)
+            .map(|_| Self::fill_incremental())
+            .find(|s| s.is_valid_solution())?
+            .to_solutions();
+
+        for _ in 0..MAX_KILLER_ATTEMPTS {
+            let mut layout = Layout::standard();
+
+            layout.add_random_cages(&solution, max_cage_size);
+
+            let layout = Arc::new(layout);
+
+            if Self::with_layout(Arc::clone(&layout)).has_unique_solution() {
+                // The puzzle has no givens, only the cages
+                let mut s = Self::with_layout(layout);
+
+                for (c, &d) in s.cells.iter_mut().zip(&solution) {
+                    c.solve_cell(d);
+                }
+
+                return Some(s);
+            }
+        }
+
+        None
+    }
+
     fn rs_cell(&mut self, c: usize) {
         self.cells[c].generate_number();
 
@@ -132,6 +383,8 @@ impl Sudoku {
             id,
             new_cell: self.cells[id],
         });
+
+        self.changes.cell_changed(&self.layout, id);
     }
 
     pub fn has_changes(&self) -> bool {
@@ -158,12 +411,15 @@ impl Sudoku {
         debug_assert!(!self.has_changes(),
             "self.cell_change_stack should be empty");
 
+        self.changes.cell_solved(&self.layout, id);
+
         let cell = self.cells[id];
+        let all  = Cell::with_digits(self.size());
 
         self.history.push(HistoryEntry {
             name,
             cells: vec![id],
-            digits: cell.get_unsolved_copy().inverse(),
+            digits: all.intersect(cell.get_unsolved_copy().inverse()),
             changes: vec![CellChange {id, new_cell: cell}]});
     }
 
@@ -213,19 +469,29 @@ impl Sudoku {
         Self::naked_single,
         Self::update_section_digit_sum,
         Self::hidden_single,
+        Self::cage_combination,
+        Self::constraint_elimination,
         Self::intersection_removal,
+        Self::innie_outie,
         Self::group_removal,
     ];
 
     fn update_section_digit_sum(&mut self) -> bool {
         // Update the contents of the variable that keeps track of
-        // the count of digits by section
+        // the count of digits by section, for the sections that
+        // changed since they were last counted
 
-        for si in SECTION_RANGE {
-            let mut temp = [0; 10];
+        let stamp = self.changes.stamp();
 
-            for ci in SECTION_INDICES[si] {
-                for di in DIGIT_RANGE {
+        for si in self.layout.section_range() {
+            if !self.changes.section_dirty(SectionRule::DigitSum, si) {
+                continue;
+            }
+
+            let mut temp = [0; MAX_DIGIT + 1];
+
+            for &ci in self.layout.section(si) {
+                for di in self.layout.digit_range() {
                     if self.cells[ci].has_digit(di) {
                         if temp[di] == 1 {
                             temp[0] += 1;
@@ -237,6 +503,8 @@ impl Sudoku {
             }
 
             self.section_digit_sum[si] = temp;
+
+            self.changes.section_checked(SectionRule::DigitSum, si, stamp);
         }
 
         // This method does not modify the sudoku board
@@ -244,22 +512,16 @@ impl Sudoku {
     }
 
     pub fn is_solved(&self) -> bool {
-        for i in 0..81 {
-            if !self.cells[i].is_solved() {
-                return false;
-            }
-        }
-
-        true
+        self.cells.iter().all(|c| c.is_solved())
     }
 
-    pub fn get_section_status(&self) -> [bool; 27] {
-        let mut section_status = [true; 27];
+    pub fn get_section_status(&self) -> Vec<bool> {
+        let mut section_status = vec![true; self.layout.section_count()];
 
-        for si in SECTION_RANGE {
+        for si in self.layout.section_range() {
             let sums = self.section_digit_sum[si];
 
-            if sums[1..].iter().any(|&x| x != 1) {
+            if sums[self.layout.digit_range()].iter().any(|&x| x != 1) {
                 section_status[si] = false;
             }
         }
@@ -267,6 +529,9 @@ impl Sudoku {
         section_status
     }
 
+    // Every section has each digit once, and none of the other rules of
+    // the layout are broken: peers that aren't in a section with the cell
+    // (anti-knight, anti-king...), cages and constraints.
     pub fn is_valid(&self) -> bool {
         debug_assert!(self.is_solved(), "only solved sudoku can be valid");
 
@@ -278,6 +543,26 @@ impl Sudoku {
             }
         }
 
-        true
+        self.peer_conflicts().is_empty()
+            && self.wrong_cages().is_empty()
+            && self.broken_constraints().is_empty()
+    }
+}
+
+
+fn parse_puzzle_str(p: &str) -> Option<Vec<usize>> {
+    let mut sol_arr = Vec::with_capacity(81);
+
+    for ch in p.chars() {
+        if ch.is_whitespace() {
+            continue;
+        }
+
+        sol_arr.push(match ch {
+            '.' => 0,
+            _ => char_to_digit(ch)?,
+        });
     }
-}
\ No newline at end of file
+
+    Some(sol_arr)
+}
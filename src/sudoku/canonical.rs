@@ -0,0 +1,250 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use crate::Sudoku;
+
+// Two boards are equivalent when one can be turned into the other without
+// changing what makes it a sudoku: relabeling the digits, reordering the
+// rows within a band and the bands themselves, doing the same with the
+// cols and stacks, and transposing the board (only if the boxes are
+// square, otherwise the boxes would change shape).
+//
+// The canonical form is the smallest of all the boards equivalent to a
+// board, read left to right and top to bottom. Two boards are equivalent
+// exactly when their canonical forms are the same. Empty cells are 0 and
+// are never relabeled, so puzzles can be compared the same way as filled
+// boards.
+//
+// For every way to order the cols, the rows are picked one at a time and
+// the digits are relabeled in the order they show up. Rows that make the
+// board bigger than the best one found so far are skipped right away,
+// which keeps a 9x9 board down to a few milliseconds. The amount of ways
+// to order the cols grows far too fast past that (about 8 million for a
+// 16x16 board), so bigger boards don't get a canonical form.
+
+const MAX_CANONICAL_SIZE: usize = 9;
+
+
+impl Sudoku {
+    // The canonical form of the solved cells of the board, in the same
+    // format as to_solutions. None for variants (whose extra sections
+    // wouldn't survive the reordering) and boards above 9x9.
+    pub fn canonical_form(&self) -> Option<Vec<usize>> {
+        if !self.layout.is_plain() || self.size() > MAX_CANONICAL_SIZE {
+            return None;
+        }
+
+        let n = self.size();
+        let (box_rows, box_cols) = self.layout.box_shape();
+
+        let board = self.to_solutions();
+
+        let mut grids = vec![board.clone()];
+
+        if box_rows == box_cols {
+            grids.push((0..n * n).map(|i| board[(i % n) * n + i / n]).collect());
+        }
+
+        let mut search = CanonicalSearch {
+            n,
+            box_rows,
+            grid: vec![0; n * n],
+            current: vec![0; n * n],
+            label: vec![0; n + 1],
+            assigned: Vec::with_capacity(n),
+            row_used: vec![false; n],
+            order: Vec::with_capacity(n),
+            best: None,
+        };
+
+        for g in &grids {
+            for cols in line_orders(n, box_cols) {
+                for ri in 0..n {
+                    for (ci, &from) in cols.iter().enumerate() {
+                        search.grid[ri * n + ci] = g[ri * n + from];
+                    }
+                }
+
+                search.fill(0, search.best.is_none());
+            }
+        }
+
+        search.best
+    }
+
+    // If the two boards are equivalent. Boards with the same layout and
+    // the same cells always are, and boards of different sizes never are.
+    // Otherwise it takes a canonical form for both, so None means it
+    // can't be told (variants and boards above 9x9).
+    pub fn is_equivalent(&self, other: &Sudoku) -> Option<bool> {
+        if Arc::ptr_eq(&self.layout, &other.layout)
+                && self.to_solutions() == other.to_solutions() {
+            return Some(true);
+        }
+
+        if self.cells.len() != other.cells.len() {
+            return Some(false);
+        }
+
+        match (self.canonical_form(), other.canonical_form()) {
+            (Some(a), Some(b)) => Some(a == b),
+            _ => None,
+        }
+    }
+}
+
+
+// Every way to order n lines that keeps the lines of each group of
+// group_size together (the cols of a stack, or the rows of a band).
+// Each order lists which of the original lines ends up at each place.
+fn line_orders(n: usize, group_size: usize) -> Vec<Vec<usize>> {
+    let mut orders = Vec::new();
+
+    for group_order in permutations(&(0..n / group_size).collect::<Vec<_>>()) {
+        let mut partial = vec![Vec::new()];
+
+        for g in group_order {
+            let inner = permutations(&(g * group_size..(g + 1) * group_size)
+                                          .collect::<Vec<_>>());
+
+            partial = partial.iter()
+                .flat_map(|p| inner.iter().map(|i| [p.as_slice(), i].concat()))
+                .collect();
+        }
+
+        orders.extend(partial);
+    }
+
+    orders
+}
+
+fn permutations(items: &[usize]) -> Vec<Vec<usize>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+
+    let mut all = Vec::new();
+
+    for i in 0..items.len() {
+        let mut rest = items.to_vec();
+        let first = rest.remove(i);
+
+        for mut p in permutations(&rest) {
+            p.insert(0, first);
+            all.push(p);
+        }
+    }
+
+    all
+}
+
+
+struct CanonicalSearch {
+    n: usize,
+    box_rows: usize,
+    grid: Vec<usize>,
+    current: Vec<usize>,
+    label: Vec<usize>,
+    assigned: Vec<usize>,
+    row_used: Vec<bool>,
+    order: Vec<usize>,
+    best: Option<Vec<usize>>,
+}
+
+/* Structure:
+    grid:
+        the board with its cols (and maybe transposition) already picked.
+
+    current:
+        the relabeled rows picked so far, the rest is left over from
+        earlier attempts.
+
+    label, assigned:
+        per digit, the label it got (0 if it hasn't shown up yet), and
+        the digits in the order they got their labels.
+
+    row_used, order:
+        which rows of grid have been picked, and in what order.
+
+    best:
+        the smallest board found so far.
+*/
+
+impl CanonicalSearch {
+    // Picks row i of the board. less is whether the rows before it are
+    // already smaller than the same rows of best (or there is no best).
+    // Returns true if best was replaced.
+    fn fill(&mut self, i: usize, mut less: bool) -> bool {
+        let n = self.n;
+
+        if i == n {
+            if less {
+                self.best = Some(self.current.clone());
+            }
+
+            return less;
+        }
+
+        let mut replaced = false;
+
+        for ri in 0..n {
+            if !self.can_follow(i, ri) {
+                continue;
+            }
+
+            let mark = self.assigned.len();
+
+            for ci in 0..n {
+                let d = self.grid[ri * n + ci];
+
+                if d != 0 && self.label[d] == 0 {
+                    self.assigned.push(d);
+                    self.label[d] = self.assigned.len();
+                }
+
+                self.current[i * n + ci] = if d == 0 { 0 } else { self.label[d] };
+            }
+
+            let cmp = match (&self.best, less) {
+                (Some(b), false) => {
+                    self.current[i * n..(i + 1) * n].cmp(&b[i * n..(i + 1) * n])
+                },
+                _ => Ordering::Less,
+            };
+
+            if cmp != Ordering::Greater {
+                self.row_used[ri] = true;
+                self.order.push(ri);
+
+                // Once best is replaced, the rows picked so far are the
+                // same as those of best, so they are no longer smaller.
+                if self.fill(i + 1, cmp == Ordering::Less) {
+                    replaced = true;
+                    less = false;
+                }
+
+                self.order.pop();
+                self.row_used[ri] = false;
+            }
+
+            for d in self.assigned.drain(mark..) {
+                self.label[d] = 0;
+            }
+        }
+
+        replaced
+    }
+
+    // The first row of a band can be any row of a band that hasn't been
+    // picked yet, the others have to come from the same band.
+    fn can_follow(&self, i: usize, ri: usize) -> bool {
+        let band = ri / self.box_rows;
+
+        if i.is_multiple_of(self.box_rows) {
+            (0..self.box_rows).all(|k| !self.row_used[band * self.box_rows + k])
+        }
+        else {
+            !self.row_used[ri] && self.order[i - 1] / self.box_rows == band
+        }
+    }
+}
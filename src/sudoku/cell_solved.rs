@@ -1,7 +1,8 @@
+use std::sync::Arc;
+
 use crate::Sudoku;
 use crate::cell::CELL_ACC;
 use crate::history::EntryType;
-use crate::index_manip::*;
 
 
 // I think it would be fairly simple to consolidate this
@@ -15,31 +16,26 @@ impl Sudoku {
         // A solved cell should remove the solution from
         // the related digits
         // 
-        // Struct memory usage: 81 bools
-        for i in 0..81 {
-            if !self.solved_cell_checked[i] && self.cells[i].is_solved() {
-
-                self.solved_cell_checked[i] = true;
-
+        // Struct memory usage: N/A
+        //
+        // Only the cells solved since the last time are looked at,
+        // in the same order as the board. Each cell is solved once,
+        // so it is only ever checked once.
+        let layout = Arc::clone(&self.layout);
+
+        for i in self.changes.take_solved() {
+            if self.cells[i].is_solved() {
                 let to_remove = self.cells[i].get_number();
 
                 if to_remove == 0 {
                     continue;
                 }
 
-                let (irow, icol, ibox) = (
-                    of_row(row_of(i)),
-                    of_col(col_of(i)),
-                    of_box(box_of(i)),
-                );
-
-                // Cell::remove_digit will check if the cell is solved;
-                // the newly solved cell won't be zeroed by this.
-                for j in 0..9 {
-                    for c in [irow[j], icol[j], ibox[j]] {
-                        if self.cells[c].remove_digit(to_remove) {
-                            self.register_change(c);
-                        }
+                // Cell::remove_digit will check if the cell is solved,
+                // so solved peers won't be zeroed by this.
+                for &c in layout.peers_of(i) {
+                    if self.cells[c].remove_digit(to_remove) {
+                        self.register_change(c);
                     }
                 }
 
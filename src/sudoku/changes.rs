@@ -0,0 +1,188 @@
+use crate::index_manip::*;
+use crate::layout::Layout;
+
+// Keeps track of which parts of the board changed, so the rules only look
+// at the parts that changed since they last looked.
+//
+// Every change to a cell (through register_change, or a cell being solved
+// by a rule) gets the next stamp, and each section of the cell remembers
+// the stamp of its last change. Each rule remembers, per section (or
+// intersection), the stamp from right before it last looked at it. If
+// nothing changed since, the rule would find the same thing as last time,
+// which was nothing, so the section is skipped.
+//
+// A rule that finds something changes the board, so whatever it found in
+// gets looked at again. Sections it never got to (because it returned
+// early) keep their old stamp, so they still count as changed.
+//
+// Rules that look at a group of cells instead of a section (a cage, an
+// innie/outie area, a constraint) do the same per group, using the stamp
+// of the last change of each cell. cell_solved and naked_single only
+// need the cells themselves, so they get the list of cells solved or
+// changed since they last ran.
+//
+// A new board starts out with every section and cell changed, so cells
+// can be set directly on it before solving. After that, cells can only
+// be changed from outside the rules through set_cell, which counts as a
+// change like any other.
+
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum SectionRule {
+    DigitSum,
+    HiddenSingle,
+    GroupRemoval,
+}
+
+const SECTION_RULES: usize = 3;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum GroupRule {
+    CageCombination,
+    InnieOutie,
+    Constraint,
+}
+
+const GROUP_RULES: usize = 3;
+
+
+#[derive(Debug, Clone)]
+pub(super) struct ChangeTracker {
+    stamp: u64,
+    section_changed: Vec<u64>,
+    section_checked: [Vec<u64>; SECTION_RULES],
+    intersection_checked: Vec<u64>,
+    cell_changed: Vec<u64>,
+    group_checked: [Vec<u64>; GROUP_RULES],
+    solved: Vec<CellIndex>,
+    changed: Vec<CellIndex>,
+}
+
+/* Structure:
+    stamp:
+        the stamp of the last change.
+
+    section_changed:
+        per section, the stamp of its last change.
+
+    section_checked:
+        per rule in SectionRule and per section, the stamp from right
+        before the rule last looked at the section.
+
+    intersection_checked:
+        the same for intersection_removal, per intersection of the layout.
+
+    cell_changed:
+        per cell, the stamp of its last change.
+
+    group_checked:
+        per rule in GroupRule and per group, the stamp from right before
+        the rule last looked at the group. The groups are numbered by the
+        rule, so the vectors grow as needed.
+
+    solved:
+        cells solved since cell_solved last ran, in no particular order
+        and maybe more than once.
+
+    changed:
+        the same for naked_single, with every changed cell.
+*/
+
+
+impl ChangeTracker {
+    pub(super) fn new(layout: &Layout) -> Self {
+        Self {
+            stamp: 1,
+            section_changed: vec![1; layout.section_count()],
+            section_checked: std::array::from_fn(|_| vec![0; layout.section_count()]),
+            intersection_checked: vec![0; layout.intersections().len()],
+            cell_changed: vec![1; layout.cell_count()],
+            group_checked: std::array::from_fn(|_| Vec::new()),
+            solved: (0..layout.cell_count()).collect(),
+            changed: (0..layout.cell_count()).collect(),
+        }
+    }
+
+    pub(super) fn stamp(&self) -> u64 {
+        self.stamp
+    }
+
+    pub(super) fn cell_changed(&mut self, layout: &Layout, ci: CellIndex) {
+        self.stamp += 1;
+
+        for &si in layout.sections_of(ci) {
+            self.section_changed[si] = self.stamp;
+        }
+
+        self.cell_changed[ci] = self.stamp;
+        self.changed.push(ci);
+    }
+
+    pub(super) fn cell_solved(&mut self, layout: &Layout, ci: CellIndex) {
+        self.cell_changed(layout, ci);
+        self.solved.push(ci);
+    }
+
+    // The cells solved since the last call, in order
+    pub(super) fn take_solved(&mut self) -> Vec<CellIndex> {
+        sorted(std::mem::take(&mut self.solved))
+    }
+
+    // The cells changed since the last call, in order
+    pub(super) fn take_changed(&mut self) -> Vec<CellIndex> {
+        sorted(std::mem::take(&mut self.changed))
+    }
+
+    pub(super) fn section_dirty(&self, rule: SectionRule, si: SecIndex) -> bool {
+        self.section_changed[si] > self.section_checked[rule as usize][si]
+    }
+
+    pub(super) fn section_checked_at(&self, rule: SectionRule, si: SecIndex) -> u64 {
+        self.section_checked[rule as usize][si]
+    }
+
+    // stamp is the stamp from before the rule started looking
+    pub(super) fn section_checked(&mut self, rule: SectionRule,
+                                  si: SecIndex, stamp: u64) {
+        self.section_checked[rule as usize][si] = stamp;
+    }
+
+    pub(super) fn intersection_dirty(&self, ii: usize, sections: [SecIndex; 2]) -> bool {
+        let changed = self.section_changed[sections[0]]
+                        .max(self.section_changed[sections[1]]);
+
+        changed > self.intersection_checked[ii]
+    }
+
+    pub(super) fn intersection_checked(&mut self, ii: usize, stamp: u64) {
+        self.intersection_checked[ii] = stamp;
+    }
+
+    pub(super) fn group_dirty(&self, rule: GroupRule, gi: usize,
+                              cells: &[CellIndex]) -> bool {
+        let checked = self.group_checked[rule as usize].get(gi).copied()
+                          .unwrap_or(0);
+
+        cells.iter().any(|&ci| self.cell_changed[ci] > checked)
+    }
+
+    // stamp is the stamp from before the rule started looking
+    pub(super) fn group_checked(&mut self, rule: GroupRule,
+                                gi: usize, stamp: u64) {
+        let checked = &mut self.group_checked[rule as usize];
+
+        if checked.len() <= gi {
+            checked.resize(gi + 1, 0);
+        }
+
+        checked[gi] = stamp;
+    }
+}
+
+
+fn sorted(mut cells: Vec<CellIndex>) -> Vec<CellIndex> {
+    cells.sort_unstable();
+    cells.dedup();
+
+    cells
+}
@@ -0,0 +1,181 @@
+use std::sync::Arc;
+use rand::seq::SliceRandom;
+
+use crate::Sudoku;
+use crate::random;
+use crate::index_manip::CellIndex;
+
+// Puzzles are made from a filled board by taking away clues for as long
+// as the board keeps a single solution.
+//
+// With a symmetry, the clues are taken away in orbits (a cell together
+// with the cell it is mapped to) so the clues that are left form the
+// same pattern on both sides, like most published puzzles.
+//
+// A clue that can't be taken away can never be taken away later either,
+// since clearing more cells only allows more solutions. So one pass over
+// the clues is enough to leave a minimal puzzle, where every clue is
+// needed. Which minimal puzzle that is depends on the order of the pass.
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Symmetry {
+    None,
+    Rotational,
+    Horizontal,
+    Vertical,
+    Diagonal,
+}
+
+/* Structure:
+    Rotational:
+        the pattern looks the same after turning the board 180°.
+
+    Horizontal:
+        the top half of the pattern is mirrored onto the bottom half.
+
+    Vertical:
+        the left half of the pattern is mirrored onto the right half.
+
+    Diagonal:
+        mirrored across the main diagonal (top left to bottom right).
+*/
+
+impl Symmetry {
+    // The position on a grid of height x width that (ri, coi) maps to
+    fn map(self, (ri, coi): (usize, usize),
+                 height: usize, width: usize) -> (usize, usize) {
+        match self {
+            Symmetry::None       => (ri, coi),
+            Symmetry::Rotational => (height - 1 - ri, width - 1 - coi),
+            Symmetry::Horizontal => (height - 1 - ri, coi),
+            Symmetry::Vertical   => (ri, width - 1 - coi),
+            Symmetry::Diagonal   => (coi, ri),
+        }
+    }
+}
+
+
+impl Sudoku {
+    // A puzzle with a unique solution made from this filled board, the
+    // clues are taken away in a random order.
+    pub fn remove_clues(&self, symmetry: Symmetry) -> Sudoku {
+        debug_assert!(self.is_valid_solution(),
+            "clues can only be removed from a valid filled board");
+
+        let mut orbits = self.orbits(symmetry);
+
+        orbits.shuffle(&mut random::rng());
+
+        let mut puzzle = self.to_solutions();
+
+        self.take_away(&mut puzzle, &orbits);
+
+        Sudoku::from_solutions_with(Arc::clone(&self.layout), &puzzle)
+    }
+
+    // Takes givens away from this puzzle until every one that is left is
+    // needed for a unique solution. Each attempt takes them away in a new
+    // random order, and the puzzle with the fewest clues is kept.
+    //
+    // Only the givens count as the puzzle, cells solved since then are
+    // left out, so the result keeps a subset of the original clues.
+    // None if the givens don't have a unique solution to begin with.
+    pub fn minimize(&self, attempts: usize) -> Option<Sudoku> {
+        let givens = self.givens();
+
+        let puzzle = Sudoku::from_solutions_with(Arc::clone(&self.layout), &givens);
+
+        if !puzzle.has_unique_solution() {
+            return None;
+        }
+
+        let mut orbits = self.orbits(Symmetry::None);
+        let mut r = random::rng();
+
+        let mut best: Option<Vec<usize>> = None;
+
+        for _ in 0..attempts.max(1) {
+            orbits.shuffle(&mut r);
+
+            let mut puzzle = givens.clone();
+
+            self.take_away(&mut puzzle, &orbits);
+
+            if best.as_ref().is_none_or(|b| clues_in(&puzzle) < clues_in(b)) {
+                best = Some(puzzle);
+            }
+        }
+
+        Some(Sudoku::from_solutions_with(Arc::clone(&self.layout), &best?))
+    }
+
+    // The amount of givens on the board, which stays the same when the
+    // puzzle gets solved.
+    pub fn clue_count(&self) -> usize {
+        clues_in(&self.givens())
+    }
+
+    // Clears each orbit in turn, unless the puzzle would be left with
+    // more than one solution.
+    fn take_away(&self, puzzle: &mut [usize], orbits: &[Vec<CellIndex>]) {
+        for orbit in orbits {
+            let kept: Vec<usize> = orbit.iter().map(|&ci| puzzle[ci]).collect();
+
+            if kept.iter().all(|&d| d == 0) {
+                continue;
+            }
+
+            for &ci in orbit {
+                puzzle[ci] = 0;
+            }
+
+            let s = Sudoku::from_solutions_with(Arc::clone(&self.layout), puzzle);
+
+            if !s.has_unique_solution() {
+                for (&ci, d) in orbit.iter().zip(kept) {
+                    puzzle[ci] = d;
+                }
+            }
+        }
+    }
+
+    // Groups the cells that have to be cleared together. Cells that are
+    // mapped to a gap in the layout are left in an orbit of their own.
+    fn orbits(&self, symmetry: Symmetry) -> Vec<Vec<CellIndex>> {
+        let (height, width) = (self.layout.height(), self.layout.width());
+
+        let mut in_orbit = vec![false; self.cells.len()];
+        let mut orbits   = Vec::new();
+
+        for ci in 0..self.cells.len() {
+            if in_orbit[ci] {
+                continue;
+            }
+
+            let mut orbit = vec![ci];
+
+            let (ri, coi) = symmetry.map(self.layout.position_of(ci),
+                                         height, width);
+
+            if let Some(mi) = self.layout.cell_at(ri, coi) {
+                if mi != ci {
+                    orbit.push(mi);
+                }
+            }
+
+            for &oi in &orbit {
+                in_orbit[oi] = true;
+            }
+
+            orbits.push(orbit);
+        }
+
+        orbits
+    }
+}
+
+
+fn clues_in(puzzle: &[usize]) -> usize {
+    puzzle.iter().filter(|&&d| d != 0).count()
+}
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use crate::Sudoku;
+use crate::cell::Cell;
+use crate::sudoku::GroupRule;
+
+// Removes the digits that can't satisfy the markings on the board
+// (thermometers, arrows, kropki dots...), see constraint.rs for how
+// each of them works.
+//
+// Each constraint that removes digits gets its own history entry, named
+// after the kind of constraint.
+
+
+impl Sudoku {
+    pub fn constraint_elimination(&mut self) -> bool {
+        let layout = Arc::clone(&self.layout);
+
+        let mut r = false;
+
+        for (ki, c) in layout.constraints().iter().enumerate() {
+            let cells = c.cells();
+
+            if !self.changes.group_dirty(GroupRule::Constraint, ki, &cells) {
+                continue;
+            }
+
+            let stamp = self.changes.stamp();
+            self.changes.group_checked(GroupRule::Constraint, ki, stamp);
+
+            if cells.iter().all(|&ci| self.cells[ci].is_solved()) {
+                continue;
+            }
+
+            let supported = c.supported_digits(&layout,
+                                |ci| self.cells[ci].get_digits());
+
+            let mut digits = 0;
+
+            for (&ci, &s) in cells.iter().zip(&supported) {
+                digits |= s;
+
+                let cell = &mut self.cells[ci];
+
+                if !cell.is_solved() && cell.intersect_with(Cell::from_digits(s)) {
+                    self.register_change(ci);
+                }
+            }
+
+            if self.add_history_entry_if_changes(c.entry_type(), cells,
+                                                 Cell::from_digits(digits)) {
+                r = true;
+            }
+        }
+
+        r
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::Sudoku;
+use crate::index_manip::*;
+use crate::history::EntryType;
+
+// A contradiction is any state of the board that can't lead to a valid
+// solution, no matter which digits are chosen afterwards:
+//     - a cell has no digits left (or was solved to 0)
+//     - a section has no cell left that can hold a digit
+//     - a section has the same digit solved in more than one cell
+//     - two peers outside of a common section (anti-knight, anti-king)
+//       have the same digit solved
+//     - a killer cage repeats a digit, or doesn't add up once filled
+//     - a constraint isn't satisfied once all of its cells are solved
+//
+// The first history entry that creates one is where the board stopped
+// being solvable, which isn't always the entry that made the mistake.
+// A RsCell guess can pick an unlucky digit that only shows up as a
+// contradiction a few rules later, so the entry found should be read as
+// a starting point for looking back through the history.
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ContradictionKind {
+    EmptyCell(CellIndex),
+    MissingDigit(SectionType, usize),
+    RepeatedDigit(SectionType, usize),
+    RepeatedPeer(CellIndex, CellIndex),
+    WrongCage(usize),
+    BrokenConstraint(usize),
+}
+
+#[derive(Debug)]
+pub struct Contradiction {
+    pub entry: usize,
+    pub name: EntryType,
+    pub cells: Vec<usize>,
+    pub kinds: Vec<ContradictionKind>,
+}
+
+/* Structure:
+    entry:
+        index into Sudoku::history of the first entry after which
+        the board could no longer be solved.
+
+    name, cells:
+        copied from that history entry, for convenience.
+
+    kinds:
+        every contradiction present on the board right after the entry
+        was applied.
+*/
+
+
+impl Sudoku {
+    pub fn find_contradiction(&self) -> Option<Contradiction> {
+        // Replays the history on a board with only the givens, the same
+        // way main.rs prints it, and checks the board after each entry.
+        // The givens aren't part of the history, so without them any
+        // contradiction with a clue would be missed.
+        let mut s = Sudoku::from_solutions_with(Arc::clone(&self.layout),
+                                                &self.givens());
+
+        for (i, h) in self.history.iter().enumerate() {
+            for cc in &h.changes {
+                s.set_cell(cc.id, cc.new_cell);
+            }
+
+            let kinds = s.get_contradictions();
+
+            if !kinds.is_empty() {
+                return Some(Contradiction {
+                    entry: i,
+                    name: h.name,
+                    cells: h.cells.clone(),
+                    kinds,
+                });
+            }
+        }
+
+        None
+    }
+
+    pub fn get_contradictions(&self) -> Vec<ContradictionKind> {
+        let mut v = Vec::new();
+
+        for (ci, cell) in self.cells.iter().enumerate() {
+            if !cell.has_digits() {
+                v.push(ContradictionKind::EmptyCell(ci));
+            }
+        }
+
+        for si in self.layout.section_range() {
+            for d in self.layout.digit_range() {
+                let mut available = 0;
+                let mut solved    = 0;
+
+                for &ci in self.layout.section(si) {
+                    let cell = self.cells[ci];
+
+                    if cell.has_digit(d) {
+                        available += 1;
+
+                        if cell.is_solved() {
+                            solved += 1;
+                        }
+                    }
+                }
+
+                if available == 0 {
+                    v.push(ContradictionKind::MissingDigit(
+                            self.layout.section_type(si), d));
+                }
+                else if solved > 1 {
+                    v.push(ContradictionKind::RepeatedDigit(
+                            self.layout.section_type(si), d));
+                }
+            }
+        }
+
+        // Peers that share a section were already reported above
+        for (a, b) in self.peer_conflicts() {
+            let shared = self.layout.sections_of(a).iter()
+                .any(|si| self.layout.sections_of(b).contains(si));
+
+            if !shared {
+                v.push(ContradictionKind::RepeatedPeer(a, b));
+            }
+        }
+
+        v.extend(self.wrong_cages().into_iter().map(ContradictionKind::WrongCage));
+
+        v.extend(self.broken_constraints().into_iter()
+                     .map(ContradictionKind::BrokenConstraint));
+
+        v
+    }
+}
+
+
+impl fmt::Display for ContradictionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContradictionKind::EmptyCell(ci) =>
+                write!(f, "cell {ci} has no digits left"),
+            ContradictionKind::MissingDigit(st, d) =>
+                write!(f, "{st} has no cell left for {d}"),
+            ContradictionKind::RepeatedDigit(st, d) =>
+                write!(f, "{st} has {d} solved more than once"),
+            ContradictionKind::RepeatedPeer(a, b) =>
+                write!(f, "peers {a} and {b} have the same digit"),
+            ContradictionKind::WrongCage(ki) =>
+                write!(f, "cage {ki} repeats a digit or has the wrong sum"),
+            ContradictionKind::BrokenConstraint(ki) =>
+                write!(f, "constraint {ki} isn't satisfied"),
+        }
+    }
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Contradiction after history entry {}: {:?}",
+                    self.entry, self.name)?;
+        write!(f, "    cells: {:?}", self.cells)?;
+
+        for k in &self.kinds {
+            write!(f, "\n    {k}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::Sudoku;
+use crate::index_manip::*;
+use crate::history::EntryType;
+use crate::sudoku::ExactSolution;
+
+// Cross-checks the logical rules against the exact solver.
+//
+// A rule should only ever remove digits that can't be part of any
+// solution. So given any one solution of a puzzle, every cell in every
+// history entry must still have (or be solved to) the digit that the
+// solution has in that cell. The first change that breaks this is
+// reported, along with the entry that made it.
+
+
+#[derive(Debug, Clone)]
+pub struct BadElimination {
+    pub entry: usize,
+    pub name: EntryType,
+    pub cells: Vec<usize>,
+    pub cell: CellIndex,
+    pub digit: usize,
+}
+
+/* Structure:
+    entry, name, cells:
+        index into Sudoku::history of the entry that made the change,
+        and the name and cells of that entry.
+
+    cell:
+        the cell that lost the digit of the solution.
+
+    digit:
+        the digit the solution has in that cell.
+*/
+
+#[derive(Debug)]
+pub enum DifferentialError {
+    NoSolution,
+    BadElimination(BadElimination),
+    Unsolved,
+}
+
+
+impl Sudoku {
+    pub fn find_bad_elimination(&self, solution: &[usize])
+                                                -> Option<BadElimination> {
+        for (i, h) in self.history.iter().enumerate() {
+            for cc in &h.changes {
+                let digit = solution[cc.id];
+
+                let bad = if cc.new_cell.is_solved() {
+                    cc.new_cell.get_number() != digit
+                }
+                else {
+                    !cc.new_cell.has_digit(digit)
+                };
+
+                if bad {
+                    return Some(BadElimination {
+                        entry: i,
+                        name: h.name,
+                        cells: h.cells.clone(),
+                        cell: cc.id,
+                        digit,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    // Solves the solved cells of this board as a puzzle with both solve()
+    // and the exact solver and checks that they agree. Returns the
+    // logically solved board if they do.
+    //
+    // A puzzle that the rules can't finish is reported as Unsolved, which
+    // isn't a bug in itself, but the eliminations made along the way are
+    // still checked first.
+    pub fn differential_check(&self) -> Result<Sudoku, DifferentialError> {
+        let puzzle = self.to_solutions();
+
+        let s = Sudoku::from_solutions_with(Arc::clone(&self.layout), &puzzle);
+
+        let solution = match s.solve_exact() {
+            ExactSolution::NoSolution => {
+                return Err(DifferentialError::NoSolution);
+            },
+            ExactSolution::Unique(s)      => s,
+            ExactSolution::Multiple(s, _) => s,
+        };
+
+        let mut s = Sudoku::from_solutions_with(Arc::clone(&self.layout), &puzzle);
+
+        s.solve();
+
+        if let Some(b) = s.find_bad_elimination(&solution) {
+            return Err(DifferentialError::BadElimination(b));
+        }
+
+        if !s.is_solved() {
+            return Err(DifferentialError::Unsolved);
+        }
+
+        Ok(s)
+    }
+}
+
+
+impl fmt::Display for BadElimination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Bad elimination in history entry {}: {:?}",
+                    self.entry, self.name)?;
+        writeln!(f, "    cells: {:?}", self.cells)?;
+        write!(f,   "    cell {} lost {}, which is in the solution",
+                    self.cell, self.digit)
+    }
+}
@@ -0,0 +1,387 @@
+use rand::seq::SliceRandom;
+
+use crate::Sudoku;
+use crate::random;
+use crate::cell::CellSize;
+use crate::index_manip::*;
+use crate::layout::Layout;
+
+// A backtracking solver that doesn't care how a human would solve the
+// board, it just finds every solution (up to a limit). It works with
+// whatever sections the layout of the board has, and keeps the digits
+// of killer cages from going past their sums. Constraints like
+// thermometers remove the digits that can't satisfy them.
+//
+// Each cell keeps a bitmask of the digits it can still have, taken from
+// the digits in its Cell. Setting a digit removes it from all peers, and
+// cells/sections that are left with a single option are set right away.
+// When nothing else can be set, the unsolved cell with the least digits
+// is picked and each of its digits is tried in turn, in order or in a
+// random order (see random_solution).
+//
+// Solved cells on the board are taken as fixed, and unsolved cells can
+// only become one of their remaining digits. A cell solved to 0 means the
+// board has no solution.
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExactSolution {
+    NoSolution,
+    Unique(Vec<usize>),
+    Multiple(Vec<usize>, Vec<usize>),
+}
+
+/* Structure:
+    Unique:
+        the only solution of the board, as the digit of each cell.
+
+    Multiple:
+        two of the solutions of the board, there may be more.
+*/
+
+fn bit_of(d: usize) -> CellSize {
+    1 << (d - 1)
+}
+
+fn digit_of(bit: CellSize) -> usize {
+    bit.trailing_zeros() as usize + 1
+}
+
+
+#[derive(Clone)]
+struct SearchState {
+    cands: Vec<CellSize>,
+    digits: Vec<usize>,
+}
+
+impl SearchState {
+    fn from_sudoku(s: &Sudoku) -> Option<Self> {
+        let mut state = Self {
+            cands: vec![0; s.cells.len()],
+            digits: vec![0; s.cells.len()],
+        };
+
+        for (ci, c) in s.cells.iter().enumerate() {
+            state.cands[ci] = c.get_digits();
+
+            if state.cands[ci] == 0 {
+                return None;
+            }
+        }
+
+        for ki in 0..s.layout.cages().len() {
+            if !state.restrict_cage(&s.layout, ki) {
+                return None;
+            }
+        }
+
+        for ki in 0..s.layout.constraints().len() {
+            if !state.restrict_constraint(&s.layout, ki) {
+                return None;
+            }
+        }
+
+        for (ci, c) in s.cells.iter().enumerate() {
+            if c.is_solved() && !state.set_digit(&s.layout, ci, c.get_number()) {
+                return None;
+            }
+        }
+
+        for ci in 0..s.cells.len() {
+            let cands = state.cands[ci];
+
+            if state.digits[ci] == 0 && cands.count_ones() == 1
+                    && !state.set_digit(&s.layout, ci, digit_of(cands)) {
+                return None;
+            }
+        }
+
+        Some(state)
+    }
+
+    // Returns false if setting the digit leads to a contradiction.
+    fn set_digit(&mut self, l: &Layout, ci: CellIndex, d: usize) -> bool {
+        if self.digits[ci] != 0 {
+            return self.digits[ci] == d;
+        }
+
+        if self.cands[ci] & bit_of(d) == 0 {
+            return false;
+        }
+
+        self.digits[ci] = d;
+        self.cands[ci]  = bit_of(d);
+
+        for &p in l.peers_of(ci) {
+            if self.digits[p] == d {
+                return false;
+            }
+
+            if self.digits[p] != 0 || self.cands[p] & bit_of(d) == 0 {
+                continue;
+            }
+
+            self.cands[p] &= !bit_of(d);
+
+            match self.cands[p].count_ones() {
+                0 => return false,
+                1 if !self.set_digit(l, p, digit_of(self.cands[p])) => {
+                    return false;
+                },
+                _ => (),
+            }
+        }
+
+        if let Some(ki) = l.cage_of(ci) {
+            if !self.restrict_cage(l, ki) {
+                return false;
+            }
+        }
+
+        l.constraints_of(ci).iter().all(|&ki| self.restrict_constraint(l, ki))
+    }
+
+    // Removes the digits that are too big or too small for the cage to
+    // still add up to its sum. Returns false if it can't.
+    fn restrict_cage(&mut self, l: &Layout, ki: usize) -> bool {
+        let cage = &l.cages()[ki];
+
+        let mut sum  = 0;
+        let mut left = 0;
+
+        for &c in &cage.cells {
+            match self.digits[c] {
+                0 => left += 1,
+                d => sum += d,
+            }
+        }
+
+        if sum > cage.sum {
+            return false;
+        }
+
+        let rest = cage.sum - sum;
+
+        if left == 0 {
+            return rest == 0;
+        }
+
+        // The smallest and biggest sums of the other unset cells,
+        // as the digits in a cage can't repeat.
+        let others   = left - 1;
+        let min_rest = others * (others + 1) / 2;
+        let max_rest = others * l.size() - others * others.saturating_sub(1) / 2;
+
+        if rest < min_rest + 1 {
+            return false;
+        }
+
+        let hi = rest - min_rest;
+        let lo = rest.saturating_sub(max_rest).max(1);
+
+        if lo > hi || lo > l.size() {
+            return false;
+        }
+
+        let mut mask = 0;
+        for d in lo..=hi.min(l.size()) {
+            mask |= bit_of(d);
+        }
+
+        for &c in &cage.cells {
+            if self.digits[c] != 0 || self.cands[c] & !mask == 0 {
+                continue;
+            }
+
+            self.cands[c] &= mask;
+
+            match self.cands[c].count_ones() {
+                0 => return false,
+                1 if !self.set_digit(l, c, digit_of(self.cands[c])) => {
+                    return false;
+                },
+                _ => (),
+            }
+        }
+
+        true
+    }
+
+    // Removes the digits that can't satisfy the constraint.
+    // Returns false if it can't be satisfied at all.
+    fn restrict_constraint(&mut self, l: &Layout, ki: usize) -> bool {
+        let c = &l.constraints()[ki];
+
+        let supported = c.supported_digits(l, |ci| self.cands[ci]);
+
+        for (ci, s) in c.cells().into_iter().zip(supported) {
+            if self.cands[ci] & !s == 0 {
+                continue;
+            }
+
+            self.cands[ci] &= s;
+
+            match self.cands[ci].count_ones() {
+                0 => return false,
+                1 if self.digits[ci] == 0
+                        && !self.set_digit(l, ci, digit_of(self.cands[ci])) => {
+                    return false;
+                },
+                _ => (),
+            }
+        }
+
+        true
+    }
+
+    // Sets every digit that only has one place left in a section.
+    // Returns false if a section has no place left for a digit.
+    fn set_hidden_singles(&mut self, l: &Layout) -> bool {
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for si in l.section_range() {
+                for d in l.digit_range() {
+                    let mut place = None;
+                    let mut count = 0;
+
+                    for &ci in l.section(si) {
+                        if self.cands[ci] & bit_of(d) != 0 {
+                            place = Some(ci);
+                            count += 1;
+                        }
+                    }
+
+                    match (count, place) {
+                        (0, _) => return false,
+                        (1, Some(ci)) if self.digits[ci] == 0 => {
+                            if !self.set_digit(l, ci, d) {
+                                return false;
+                            }
+                            changed = true;
+                        },
+                        _ => (),
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    fn pick_cell(&self) -> Option<CellIndex> {
+        let mut best = None;
+        let mut best_count = u32::MAX;
+
+        for ci in 0..self.digits.len() {
+            if self.digits[ci] != 0 {
+                continue;
+            }
+
+            let count = self.cands[ci].count_ones();
+
+            if count < best_count {
+                best = Some(ci);
+                best_count = count;
+            }
+        }
+
+        best
+    }
+}
+
+
+struct ExactSolver<'a> {
+    layout: &'a Layout,
+    limit: usize,
+    shuffle: bool,
+    found: Vec<Vec<usize>>,
+}
+
+impl ExactSolver<'_> {
+    fn search(&mut self, mut state: SearchState) {
+        if !state.set_hidden_singles(self.layout) {
+            return;
+        }
+
+        let Some(ci) = state.pick_cell() else {
+            self.found.push(state.digits);
+            return;
+        };
+
+        let mut digits: Vec<usize> = self.layout.digit_range()
+            .filter(|&d| state.cands[ci] & bit_of(d) != 0)
+            .collect();
+
+        if self.shuffle {
+            digits.shuffle(&mut random::rng());
+        }
+
+        for d in digits {
+            if self.found.len() >= self.limit {
+                break;
+            }
+
+            let mut next = state.clone();
+
+            if next.set_digit(self.layout, ci, d) {
+                self.search(next);
+            }
+        }
+    }
+}
+
+
+impl Sudoku {
+    pub fn solve_exact(&self) -> ExactSolution {
+        let found = self.find_solutions(2);
+
+        let mut found = found.into_iter();
+
+        match (found.next(), found.next()) {
+            (None, _)          => ExactSolution::NoSolution,
+            (Some(a), None)    => ExactSolution::Unique(a),
+            (Some(a), Some(b)) => ExactSolution::Multiple(a, b),
+        }
+    }
+
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        self.find_solutions(limit).len()
+    }
+
+    pub fn has_unique_solution(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+
+    pub fn find_solutions(&self, limit: usize) -> Vec<Vec<usize>> {
+        self.search_solutions(limit, false)
+    }
+
+    // The first solution found when the digits of each guess are tried
+    // in a random order, None if the board has no solution. Every
+    // solution can come up, but not all equally often (see sampling.rs).
+    pub fn random_solution(&self) -> Option<Vec<usize>> {
+        self.search_solutions(1, true).pop()
+    }
+
+    fn search_solutions(&self, limit: usize, shuffle: bool) -> Vec<Vec<usize>> {
+        let mut solver = ExactSolver {
+            layout: &self.layout,
+            limit,
+            shuffle,
+            found: Vec::new(),
+        };
+
+        if limit == 0 {
+            return solver.found;
+        }
+
+        if let Some(state) = SearchState::from_sudoku(self) {
+            solver.search(state);
+        }
+
+        solver.found
+    }
+}
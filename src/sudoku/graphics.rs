@@ -1,8 +1,9 @@
 use std::fmt;
 
 use crate::Sudoku;
-use crate::cell::{DIGIT_RANGE, Cell};
-use crate::index_manip::*;
+use crate::cell::{Cell, digit_to_char};
+use crate::constraint::{Constraint, Parity};
+use crate::index_manip::CellIndex;
 
 // Shamelessly ripped from:
 // https://codegolf.stackexchange.com/questions/126930/draw-a-sudoku-board-using-line-drawing-characters
@@ -121,6 +122,36 @@ impl Sudoku {
         }
     }
 
+    pub fn print_contradiction(&self) {
+        match self.find_contradiction() {
+            Some(c) => {
+                println!("{c}");
+                println!("{}", self.history[c.entry].display(self.size()));
+            },
+            None => println!("No contradiction found in history"),
+        }
+    }
+
+    // Prints the index of the cage of each cell, followed by the sums
+    pub fn print_cages(&self) {
+        for ri in 0..self.layout.height() {
+            for coi in 0..self.layout.width() {
+                match self.layout.cell_at(ri, coi) {
+                    Some(ci) => match self.layout.cage_of(ci) {
+                        Some(ki) => print!("{ki:>4}"),
+                        None     => print!("   ."),
+                    },
+                    None => print!("    "),
+                }
+            }
+            println!();
+        }
+
+        for (ki, cage) in self.layout.cages().iter().enumerate() {
+            println!("Cage {ki}: {} {:?}", cage.sum, cage.cells);
+        }
+    }
+
     pub fn print_invalid_cells(&self) {
         debug_assert!(self.is_solved(), "only solved sudoku can be invalid");
 
@@ -128,11 +159,22 @@ impl Sudoku {
 
         println!("Invalid solutions:");
 
-        println!("       | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9 |");
+        print!("       |");
+        for d in self.layout.digit_range() {
+            print!("{:^3}|", digit_to_char(d));
+        }
+        println!();
+
+        let separator = "---|".repeat(self.size());
+
+        let mut last_type = None;
+
+        for si in self.layout.section_range() {
+            let st = self.layout.section_type(si);
 
-        for si in SECTION_RANGE {
-            if si % 9 == 0 {
-                println!("       |---|---|---|---|---|---|---|---|---|");
+            if last_type != Some(std::mem::discriminant(&st)) {
+                println!("       |{separator}");
+                last_type = Some(std::mem::discriminant(&st));
             }
 
             let sums = self.section_digit_sum[si];
@@ -141,18 +183,38 @@ impl Sudoku {
                 continue;
             }
 
-            print!("{}: |", of_section(si));
+            print!("{st}: |");
 
-            for j in DIGIT_RANGE {
+            for j in self.layout.digit_range() {
                 if sums[j] == 1 {
                     print!("   |");
                 }
                 else {
-                    print!(" {} |", sums[j]);
+                    print!("{:^3}|", sums[j]);
                 }
             }
             println!();
         }
+
+        // Peers outside of the sections, cages and constraints don't
+        // show up in the table above
+        let pairs = self.peer_conflicts();
+
+        if !pairs.is_empty() {
+            println!("Peers with the same digit: {pairs:?}");
+        }
+
+        let cages = self.wrong_cages();
+
+        if !cages.is_empty() {
+            println!("Wrong cages: {cages:?}");
+        }
+
+        let constraints = self.broken_constraints();
+
+        if !constraints.is_empty() {
+            println!("Broken constraints: {constraints:?}");
+        }
     }
 }
 
@@ -160,59 +222,231 @@ impl Sudoku {
 impl fmt::Display for Sudoku {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // This is only intended to display a completed sudoku.
+        // Even cells are shown as [d] and odd cells as (d).
+        //
+        // The alternate form ({:#}) only shows the givens, which is the
+        // puzzle the board was made from.
+
+        let (height, width) = (self.layout.height(), self.layout.width());
+
+        for ri in 0..height {
+            self.write_border(f, ri, 3)?;
+
+            for ci in 0..width {
+                write!(f, "{}", self.get_separator(ri, ci, true))?;
+
+                match self.layout.cell_at(ri, ci) {
+                    Some(c) => {
+                        let cell = self.cells[c];
+
+                        let d = if f.alternate() && !cell.is_given() {
+                            '0'
+                        }
+                        else {
+                            digit_to_char(cell.get_number())
+                        };
+
+                        match self.parity_of(c) {
+                            Some(Parity::Even) => write!(f, "[{d}]")?,
+                            Some(Parity::Odd)  => write!(f, "({d})")?,
+                            None               => write!(f, " {d} ")?,
+                        }
+                    },
+                    None => write!(f, "   ")?,
+                }
+            }
+
+            writeln!(f, "{}", self.get_separator(ri, width, true))?;
+        }
+
+        self.write_border(f, height, 3)
+    }
+}
+
+
+// Helper methods for drawing the borders
+//
+// Thick borders are drawn between cells of different regions, so they
+// follow the boxes of the standard layout and the regions of jigsaw ones.
+// The edges of the board (and of the gaps between grids) are thick too.
+//
+// Inequalities are drawn on the line between their cells, pointing at
+// the smaller digit: < or > between cells of a row, ^ or v between cells
+// of a col.
+#[derive(PartialEq, Clone, Copy)]
+enum Line {
+    Empty,
+    Thin,
+    Thick,
+}
+
+impl Sudoku {
+    fn line_between(&self, c1: Option<CellIndex>,
+                           c2: Option<CellIndex>) -> Line {
+        match (c1, c2) {
+            (None, None) => Line::Empty,
+            (Some(c1), Some(c2))
+                if self.layout.region_of(c1) == self.layout.region_of(c2) => {
+                Line::Thin
+            },
+            _ => Line::Thick,
+        }
+    }
+
+    // The line left of position ci in row ri, ci == width is the right edge
+    fn vertical_line(&self, ri: usize, ci: usize) -> Line {
+        let left = if ci == 0 { None } else { self.layout.cell_at(ri, ci-1) };
+
+        self.line_between(left, self.layout.cell_at(ri, ci))
+    }
+
+    // The line above position ci in row ri, ri == height is the bottom edge
+    fn horizontal_line(&self, ri: usize, ci: usize) -> Line {
+        let up = if ri == 0 { None } else { self.layout.cell_at(ri-1, ci) };
 
-        let top  = "╔═══╤═══╤═══╦═══╤═══╤═══╦═══╤═══╤═══╗";
-        let mid  = "╟───┼───┼───╫───┼───┼───╫───┼───┼───╢";
-        let boxl = "╠═══╪═══╪═══╬═══╪═══╪═══╬═══╪═══╪═══╣";
-        let bot  = "╚═══╧═══╧═══╩═══╧═══╧═══╩═══╧═══╧═══╝";
-
-        writeln!(f, "{}", top)?;
-
-        for i in 0..9 {
-            writeln!(
-                f, "║ {} │ {} │ {} ║ {} │ {} │ {} ║ {} │ {} │ {} ║",
-                self.cells[i*9    ].get_number(),
-                self.cells[i*9 + 1].get_number(),
-                self.cells[i*9 + 2].get_number(),
-                self.cells[i*9 + 3].get_number(),
-                self.cells[i*9 + 4].get_number(),
-                self.cells[i*9 + 5].get_number(),
-                self.cells[i*9 + 6].get_number(),
-                self.cells[i*9 + 7].get_number(),
-                self.cells[i*9 + 8].get_number(),
-            )?;
-
-            if i == 8 {
-                writeln!(f, "{}", bot)?;
+        self.line_between(up, self.layout.cell_at(ri, ci))
+    }
+
+    // If there is an inequality between c1 and c2, whether c1 is the
+    // smaller one
+    fn less_than(&self, c1: Option<CellIndex>,
+                        c2: Option<CellIndex>) -> Option<bool> {
+        let (c1, c2) = (c1?, c2?);
+
+        self.layout.constraints_of(c1).iter().find_map(|&i| {
+            match self.layout.constraints()[i] {
+                Constraint::LessThan([a, b]) if [a, b] == [c1, c2] => Some(true),
+                Constraint::LessThan([a, b]) if [a, b] == [c2, c1] => Some(false),
+                _ => None,
             }
-            else if i % 3 == 2 {
-                writeln!(f, "{}", boxl)?;
+        })
+    }
+
+    fn parity_of(&self, c: CellIndex) -> Option<Parity> {
+        self.layout.constraints_of(c).iter().find_map(|&i| {
+            match self.layout.constraints()[i] {
+                Constraint::Parity(p, _) => Some(p),
+                _ => None,
             }
-            else {
-                writeln!(f, "{}", mid)?;
+        })
+    }
+
+    // The separator left of position ci in row ri, marks is whether
+    // inequalities should be drawn on it
+    fn get_separator(&self, ri: usize, ci: usize, marks: bool) -> char {
+        if marks && ci > 0 {
+            let left = self.layout.cell_at(ri, ci-1);
+
+            match self.less_than(left, self.layout.cell_at(ri, ci)) {
+                Some(true)  => return '<',
+                Some(false) => return '>',
+                None => (),
             }
         }
 
-        Ok(())
+        match self.vertical_line(ri, ci) {
+            Line::Thick => '║',
+            Line::Thin  => '│',
+            Line::Empty => ' ',
+        }
+    }
+
+    // The corner at the top left of position ci in row ri
+    fn get_junction(&self, ri: usize, ci: usize) -> char {
+        use Line::*;
+
+        let up    = if ri == 0 { Empty } else { self.vertical_line(ri-1, ci) };
+        let down  = self.vertical_line(ri, ci);
+        let left  = if ci == 0 { Empty } else { self.horizontal_line(ri, ci-1) };
+        let right = self.horizontal_line(ri, ci);
+
+        // There are no characters for a line that changes thickness
+        // halfway through, so the thick side wins.
+        let v = if up == Thick || down  == Thick { Thick } else { Thin };
+        let h = if left == Thick || right == Thick { Thick } else { Thin };
+
+        match (up != Empty, down != Empty, left != Empty, right != Empty) {
+            (false, false, false, false) => ' ',
+            (_, _, false, false) => if v == Thick { '║' } else { '│' },
+            (false, false, _, _) => if h == Thick { '═' } else { '─' },
+            (false, true, false, true) => '╔',
+            (false, true, true, false) => '╗',
+            (true, false, false, true) => '╚',
+            (true, false, true, false) => '╝',
+            (false, true, true, true)  => if v == Thick { '╦' } else { '╤' },
+            (true, false, true, true)  => if v == Thick { '╩' } else { '╧' },
+            (true, true, false, true)  => if h == Thick { '╠' } else { '╟' },
+            (true, true, true, false)  => if h == Thick { '╣' } else { '╢' },
+            _ => match (v, h) {
+                (Thick, Thick) => '╬',
+                (Thick, _)     => '╫',
+                (_, Thick)     => '╪',
+                _              => '┼',
+            },
+        }
+    }
+
+    fn write_border(&self, f: &mut fmt::Formatter,
+                           ri: usize, width: usize) -> fmt::Result {
+        for ci in 0..self.layout.width() {
+            let line = match self.horizontal_line(ri, ci) {
+                Line::Thick => "═",
+                Line::Thin  => "─",
+                Line::Empty => " ",
+            };
+
+            let up = if ri == 0 { None } else { self.layout.cell_at(ri-1, ci) };
+
+            let mark = match self.less_than(up, self.layout.cell_at(ri, ci)) {
+                Some(true)  => '^',
+                Some(false) => 'v',
+                None => {
+                    write!(f, "{}{}", self.get_junction(ri, ci),
+                                      line.repeat(width))?;
+                    continue;
+                },
+            };
+
+            let left = width / 2;
+
+            write!(f, "{}{}{mark}{}", self.get_junction(ri, ci),
+                      line.repeat(left), line.repeat(width - 1 - left))?;
+        }
+
+        writeln!(f, "{}", self.get_junction(ri, self.layout.width()))
     }
 }
 
 
 // Helper methods for Debug print
+//
+// The digits of each cell are laid out in the same shape as a box,
+// so a 9x9 board has 3x3 digits per cell and a 6x6 board has 2x3.
 impl Sudoku {
     fn write_digit_row(&self, f: &mut fmt::Formatter,
                               c: Cell, dr: usize) -> fmt::Result {
+        let (rows, cols) = self.layout.box_shape();
+
         if c.is_solved() {
-            if dr == 1 {
-                write!(f, "  <{}> ", c.get_number())?;
+            let width = 2 * cols;
+            let left  = (width - 3).div_ceil(2);
+
+            // Givens are {d}, cells that were solved are <d>
+            // ([d] is an even cell in Display)
+            let (open, close) = if c.is_given() { ('{', '}') } else { ('<', '>') };
+
+            if dr == rows / 2 {
+                write!(f, "{:left$}{open}{}{close}{:right$}", "",
+                          digit_to_char(c.get_number()), "",
+                          right = width - 3 - left)?;
             }
             else {
-                write!(f, "      ")?;
+                write!(f, "{:width$}", "")?;
             }
         }
         else {
-            for i in 1..=3 {
-                self.write_num(f, c, dr*3 + i)?;
+            for i in 1..=cols {
+                self.write_num(f, c, dr*cols + i)?;
             }
         }
 
@@ -222,7 +456,7 @@ impl Sudoku {
     fn write_num(&self, f: &mut fmt::Formatter,
                         c: Cell, i: usize) -> fmt::Result {
         if c.has_digit(i) {
-            write!(f, " {i}")
+            write!(f, " {}", digit_to_char(i))
         }
         else {
             write!(f, "  ")
@@ -235,48 +469,33 @@ impl fmt::Debug for Sudoku {
         // This shows all cells as a collection of digits,
         // regardless of whether they are already solved.
 
-        let top  = "╔═══════╤═══════╤═══════╦═══════╤═══════╤═══════╦═══════╤═══════╤═══════╗";
-        let mid  = "╟───────┼───────┼───────╫───────┼───────┼───────╫───────┼───────┼───────╢";
-        let boxl = "╠═══════╪═══════╪═══════╬═══════╪═══════╪═══════╬═══════╪═══════╪═══════╣";
-        let bot  = "╚═══════╧═══════╧═══════╩═══════╧═══════╧═══════╩═══════╧═══════╧═══════╝";
-        //║       │       │       ║       │       │       ║       │       │       ║
-
-        writeln!(f, "{}", top)?;
+        let (height, grid_width) = (self.layout.height(), self.layout.width());
+        let (rows, cols) = self.layout.box_shape();
+        let width = 2 * cols + 1;
 
-        for ri in 0..9 {
-            for digit_row in 0..3 {
-                for ci in 0..9 {
+        for ri in 0..height {
+            self.write_border(f, ri, width)?;
 
-                    if ci != 0 {
-                        write!(f, " ")?;
-                    }
+            for digit_row in 0..rows {
+                for ci in 0..grid_width {
+                    let middle = digit_row == rows / 2;
 
-                    if ci % 3 == 0 {
-                        write!(f, "║")?;
-                    }
-                    else {
-                        write!(f, "│")?;
-                    }
+                    write!(f, "{}", self.get_separator(ri, ci, middle))?;
 
-                    let cell = self.cells[ri*9 + ci];
+                    match self.layout.cell_at(ri, ci) {
+                        Some(c) => {
+                            self.write_digit_row(f, self.cells[c], digit_row)?;
 
-                    self.write_digit_row(f, cell, digit_row)?;
+                            write!(f, " ")?;
+                        },
+                        None => write!(f, "{:width$}", "")?,
+                    }
                 }
 
-                writeln!(f, " ║")?;
-            }
-
-            if ri == 8 {
-                writeln!(f, "{}", bot)?;
-            }
-            else if ri % 3 == 2 {
-                writeln!(f, "{}", boxl)?;
-            }
-            else {
-                writeln!(f, "{}", mid)?;
+                writeln!(f, "{}", self.get_separator(ri, grid_width, false))?;
             }
         }
 
-        Ok(())
+        self.write_border(f, height, width)
     }
-}
\ No newline at end of file
+}
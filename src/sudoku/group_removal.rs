@@ -2,8 +2,9 @@ use std::cmp::{max, min};
 
 use crate::Sudoku;
 use crate::cell::{Cell, CELL_ACC, CELL_EMPTY};
-use crate::index_manip::*;
+use crate::index_manip::CellIndex;
 use crate::history::EntryType;
+use crate::sudoku::SectionRule;
 
 /* Goal of group_removal:
     Look for groups of digits in the sudoku board.
@@ -47,6 +48,8 @@ use crate::history::EntryType;
 */
 
 
+// The search is exponential in the size of the group, so boards bigger
+// than 9x9 only look for groups up to the same size as a 9x9 board.
 const MIN_GROUP_SIZE:       usize = 2;
 const MAX_NAKED_GROUP_SIZE: usize = 7;
 
@@ -78,7 +81,7 @@ impl Subsection {
     fn add_cell(&mut self, cid: usize, c: Cell) {
         self.acc.union_with(c);
 
-        if usize::from(c.get_count()) < MAX_NAKED_GROUP_SIZE {
+        if (c.get_count() as usize) < MAX_NAKED_GROUP_SIZE {
             self.cand_ids.push(cid);
             self.cand_cells.push(c);
         }
@@ -91,7 +94,7 @@ impl Subsection {
     }
 
     fn calc_mgs(&mut self) {
-        self.mgs = min(self.cand_ids.len(),
+        self.mgs = min(min(self.cand_ids.len(), MAX_NAKED_GROUP_SIZE),
                        max_group_size_of(self.total_cells));
     }
 
@@ -102,7 +105,7 @@ impl Subsection {
     fn find_group_r(&self, acc: Cell,  cell_count: usize,
                            cid: usize, max_depth: usize) -> Option<Vec<usize>> {
         // Rust's handling of integers is kinda getting on my nerves
-        let acc_count = usize::from(acc.get_count());
+        let acc_count = acc.get_count() as usize;
 
         let max_count = max(acc_count, cell_count);
 
@@ -157,10 +160,19 @@ impl Subsection {
 
 impl Sudoku {
     pub fn group_removal(&mut self) -> bool {
-        for si in SECTION_RANGE {
-            let sec_cells = &SECTION_INDICES[si];
+        for si in self.layout.section_range() {
+            if !self.changes.section_dirty(SectionRule::GroupRemoval, si) {
+                continue;
+            }
+
+            // Nothing on the board changes unless a group is found, and
+            // then the section has changed.
+            let stamp = self.changes.stamp();
+            self.changes.section_checked(SectionRule::GroupRemoval, si, stamp);
+
+            let sec_cells = self.layout.section(si).to_vec();
 
-            let mut vec_sc = Vec::from(sec_cells);
+            let mut vec_sc = sec_cells.clone();
 
             let mut i = 0;
             while i < vec_sc.len() {
@@ -196,7 +208,7 @@ impl Sudoku {
                     // This if stmt is basically a formality, if the
                     // algorithm finds a group then it is one that
                     // changes the board.
-                    if self.handle_group(sec_cells, g) {
+                    if self.handle_group(&sec_cells, g) {
                         return true;
                     }
                 }
@@ -242,7 +254,7 @@ impl Sudoku {
         sbs
     }
 
-    fn handle_group(&mut self, section: &[usize; 9], g: Vec<usize>) -> bool {
+    fn handle_group(&mut self, section: &[CellIndex], g: Vec<usize>) -> bool {
         let mut acc = CELL_ACC;
 
         for cid in &g {
@@ -251,7 +263,7 @@ impl Sudoku {
 
         let inv_acc = acc.inverse();
 
-        for sid in *section {
+        for &sid in section {
             let cell = self.cells[sid];
 
             if !cell.is_solved() && cell.has_intersection(acc)
@@ -1,7 +1,6 @@
 use crate::Sudoku;
-use crate::index_manip::*;
-use crate::cell::DIGIT_RANGE;
 use crate::history::EntryType;
+use crate::sudoku::SectionRule;
 
 impl Sudoku {
     pub fn hidden_single(&mut self) -> bool {
@@ -10,10 +9,16 @@ impl Sudoku {
         // Struct memory usage: N/A, won't save time using it
         let mut r = false;
 
-        for si in SECTION_RANGE {
+        let stamp = self.changes.stamp();
+
+        for si in self.layout.section_range() {
+            if !self.changes.section_dirty(SectionRule::HiddenSingle, si) {
+                continue;
+            }
+
             let sums = self.section_digit_sum[si];
 
-            for j in DIGIT_RANGE {
+            for j in self.layout.digit_range() {
                 let count = sums[j];
 
                 if count == 1 {
@@ -24,13 +29,21 @@ impl Sudoku {
                     }
                 }
             }
+
+            // The sums are from the last time they were counted, so the
+            // section is only checked up to then. Cells solved during
+            // this pass come after the stamp, so they are looked at again.
+            let counted = self.changes.section_checked_at(SectionRule::DigitSum, si);
+
+            self.changes.section_checked(SectionRule::HiddenSingle, si,
+                                         stamp.min(counted));
         }
 
         r
     }
 
     fn find_hidden_single(&mut self, si: usize, digit: usize) -> bool {
-        for ci in SECTION_INDICES[si] {
+        for &ci in self.layout.section(si) {
             if !self.cells[ci].is_solved()
                     && self.cells[ci].has_digit(digit) {
 
@@ -0,0 +1,264 @@
+use std::fmt;
+
+use crate::Sudoku;
+use crate::cell::CellSize;
+use crate::index_manip::*;
+use crate::layout::Layout;
+
+// Measures how far a board is from being a valid sudoku.
+//
+// Only solved cells with a digit are considered to be "set". Unsolved
+// cells and cells solved to 0 are free to become any digit, so a partial
+// board with no conflicts has an incorrectness of 0 as long as it can
+// still be completed.
+//
+// Any set cell can be changed, givens included, since a board read with
+// from_solutions or from_puzzle_str has every set cell as a given.
+// get_incorrectness_keeping_givens never changes the givens instead, for
+// boards whose givens are known to be part of the puzzle. Then a board
+// whose givens can't be completed has no amount of changes that fixes it.
+
+
+#[derive(Debug, Clone)]
+pub struct Incorrectness {
+    pub conflicting_cells: usize,
+    pub duplicate_digits: Vec<usize>,
+    pub min_changes: Option<usize>,
+}
+
+/* Structure:
+    conflicting_cells:
+        amount of set cells that share their digit with another set cell
+        in the same section.
+
+    duplicate_digits:
+        per section, the amount of set cells that would need to go
+        for each digit to be set at most once. Uses the same section
+        order as the layout of the board.
+
+    min_changes:
+        the least amount of set cells that need a different digit
+        for the board to be completed into a valid sudoku.
+        None if that amount is above the limit given to the search,
+        or if the givens are kept and can't be completed at all.
+*/
+
+impl Incorrectness {
+    pub fn total_duplicates(&self) -> usize {
+        self.duplicate_digits.iter().sum()
+    }
+
+    // Each changed cell can fix at most one duplicate in each of the
+    // sections it belongs to, so at least this many changes are needed.
+    fn lower_bound(&self, l: &Layout) -> usize {
+        let mut most_sections = 1;
+
+        for ci in 0..l.cell_count() {
+            most_sections = most_sections.max(l.sections_of(ci).len());
+        }
+
+        self.total_duplicates().div_ceil(most_sections)
+    }
+}
+
+
+impl Sudoku {
+    pub fn get_incorrectness(&self, max_changes: usize) -> Incorrectness {
+        self.incorrectness(max_changes, false)
+    }
+
+    // Same as get_incorrectness, but only cells that aren't givens can
+    // be changed.
+    pub fn get_incorrectness_keeping_givens(&self, max_changes: usize) -> Incorrectness {
+        self.incorrectness(max_changes, true)
+    }
+
+    fn incorrectness(&self, max_changes: usize, keep_givens: bool) -> Incorrectness {
+        let board = self.to_solutions();
+        let given: Vec<bool> = self.cells.iter()
+            .map(|c| keep_givens && c.is_given())
+            .collect();
+
+        let mut inc = Incorrectness {
+            conflicting_cells: 0,
+            duplicate_digits: vec![0; self.layout.section_count()],
+            min_changes: None,
+        };
+
+        let mut conflicting = vec![false; self.cells.len()];
+
+        for c in self.verify() {
+            inc.duplicate_digits[c.section] += c.cells.len() - 1;
+
+            for ci in c.cells {
+                conflicting[ci] = true;
+            }
+        }
+
+        inc.conflicting_cells = conflicting.iter().filter(|&&b| b).count();
+
+        // Iterative deepening, so the first budget that works
+        // is the smallest one.
+        for budget in inc.lower_bound(&self.layout)..=max_changes {
+            let mut search = ChangeSearch::new(&self.layout, &board,
+                                               &given);
+
+            if search.fill(budget) {
+                inc.min_changes = Some(budget);
+                break;
+            }
+        }
+
+        inc
+    }
+}
+
+
+// Backtracking search for a valid completion of a board that differs
+// from it in at most `budget` set cells. A completion has to keep to
+// every rule of the layout, so a digit is only placed if none of the
+// peers of its cell have it and its cage and constraints can still be
+// satisfied.
+struct ChangeSearch<'a> {
+    layout: &'a Layout,
+    original: &'a [usize],
+    given: &'a [bool],
+    filled: Vec<usize>,
+    all_digits: CellSize,
+}
+
+impl<'a> ChangeSearch<'a> {
+    fn new(layout: &'a Layout, original: &'a [usize],
+                               given: &'a [bool]) -> Self {
+        Self {
+            layout,
+            original,
+            given,
+            filled: vec![0; original.len()],
+            all_digits: ((1 << layout.size()) - 1) << 1,
+        }
+    }
+
+    fn available(&self, ci: CellIndex) -> CellSize {
+        let mut used = 0;
+
+        for &p in self.layout.peers_of(ci) {
+            used |= 1 << self.filled[p];
+        }
+
+        !used & self.all_digits
+    }
+
+    // If the cage and the constraints of the cell can still be satisfied
+    // by the digits filled in so far.
+    fn fits(&self, ci: CellIndex) -> bool {
+        let size = self.layout.size();
+
+        if let Some(ki) = self.layout.cage_of(ci) {
+            let cage = &self.layout.cages()[ki];
+
+            let sum: usize = cage.cells.iter().map(|&c| self.filled[c]).sum();
+            let left = cage.cells.iter().filter(|&&c| self.filled[c] == 0).count();
+
+            if sum + left > cage.sum || sum + left * size < cage.sum {
+                return false;
+            }
+        }
+
+        for &ki in self.layout.constraints_of(ci) {
+            let c = &self.layout.constraints()[ki];
+
+            // Constraints are checked in the order of their cells, so
+            // only the cells filled from the start on can be checked.
+            let digits: Vec<usize> = c.cells().iter()
+                .map(|&c| self.filled[c])
+                .take_while(|&d| d != 0)
+                .collect();
+
+            if !(1..=digits.len()).all(|i| c.allows(&digits[..i], size)) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Fills the rest of the board with ci set to d, or leaves ci empty
+    // again if that doesn't work.
+    fn try_digit(&mut self, ci: CellIndex, d: usize, budget: usize) -> bool {
+        self.filled[ci] = d;
+
+        if self.fits(ci) && self.fill(budget) {
+            return true;
+        }
+
+        self.filled[ci] = 0;
+
+        false
+    }
+
+    fn fill(&mut self, budget: usize) -> bool {
+        // Pick the empty cell with the least digits available
+        let mut best = None;
+        let mut best_count = u32::MAX;
+
+        for ci in 0..self.filled.len() {
+            if self.filled[ci] != 0 {
+                continue;
+            }
+
+            let count = self.available(ci).count_ones();
+
+            if count < best_count {
+                best = Some(ci);
+                best_count = count;
+
+                if count == 0 {
+                    return false;
+                }
+            }
+        }
+
+        let Some(ci) = best else {
+            // Every cell is filled
+            return true;
+        };
+
+        let avail = self.available(ci);
+        let o = self.original[ci];
+
+        // Keeping the original digit is free, so try it first
+        if o != 0 && avail & (1 << o) != 0 && self.try_digit(ci, o, budget) {
+            return true;
+        }
+
+        let cost = usize::from(o != 0);
+
+        if cost > budget || self.given[ci] {
+            return false;
+        }
+
+        for d in self.layout.digit_range() {
+            if d != o && avail & (1 << d) != 0
+                    && self.try_digit(ci, d, budget - cost) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+
+impl fmt::Display for Incorrectness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Incorrectness:")?;
+        writeln!(f, "    conflicting cells: {}", self.conflicting_cells)?;
+        writeln!(f, "    duplicate digits:  {}", self.total_duplicates())?;
+
+        match self.min_changes {
+            Some(c) => write!(f, "    min changes:       {c}"),
+            None    => write!(f, "    min changes:       above limit"),
+        }
+    }
+}
@@ -1,141 +1,113 @@
+use std::sync::Arc;
+
 use crate::Sudoku;
 use crate::index_manip::*;
-use crate::cell::{CELL_ACC, Cell, CELL_EMPTY};
+use crate::cell::{CELL_ACC, Cell};
 use crate::sudoku::EntryType;
 
 // Definition of pointed group:
 // A set of digits within cells that share the same box and row or column,
 // where the digits are only present within the set and not the box.
-// 
+//
 // This means the digits within the same row/column can be eliminated.
 
 // Definition of box-line reduction:
 // A set of digits within cells that share the same box and row or column,
 // where the digits are only present within the set and not the row/column.
-// 
+//
 // This means the digits within the same box can be eliminated.
 
+// Both of these work the same way for any two sections that share cells
+// (jigsaw regions, diagonals, etc), so the rule looks at every
+// intersection in the layout instead of just the box/line ones.
+// The entry is named after the section the digits are confined to:
+// a box or region makes it a pointed group, anything else is treated
+// as a line.
 
-// TODO: Create BoxDivision type?
-const INDEX_MATRIX: [[[[usize; 3]; 3]; 3]; 6] = make_id_matrix();
 
-const fn make_id_matrix() -> [[[[usize; 3]; 3]; 3]; 6] {
-    let mut mat = [[[[0; 3]; 3]; 3]; 6];
+impl Sudoku {
+    pub fn intersection_removal(&mut self) -> bool {
+        let layout = Arc::clone(&self.layout);
 
-    let mut i = 0;
-    while i < 3 {
-        mat[i]   = get_box_ids(i, SECTION_ROW_START);
-        mat[i+3] = get_box_ids(i, SECTION_COL_START);
-        i += 1;
-    }
+        for (ii, inter) in layout.intersections().iter().enumerate() {
+            let [sa, sb] = inter.sections;
 
-    mat
-}
+            // Only the cells of the two sections matter, so if neither
+            // changed since the last look, there is still nothing to find.
+            if !self.changes.intersection_dirty(ii, inter.sections) {
+                continue;
+            }
 
-const fn get_box_ids(i: usize, offset: usize) -> [[[usize; 3]; 3]; 3] {
-    let mut mat = [[[0; 3]; 3]; 3];
+            let stamp = self.changes.stamp();
+            self.changes.intersection_checked(ii, stamp);
 
-    let mut s = 0; while s < 3 {
-        let mut os = 0; while os < 3 {
-            let mut c = 0; while c < 3 {
-                mat[s][os][c] = SECTION_INDICES[offset+i*3+s][os*3+c];
-                c += 1;
-            }
-            os += 1;
-        }
-        s += 1;
-    }
+            let trio    = self.get_union_of(&inter.cells);
+            let other_a = self.get_union_of(&inter.rest[0]);
+            let other_b = self.get_union_of(&inter.rest[1]);
 
-    mat
-}
+            let ds = trio.intersect(other_a.xor(other_b));
 
+            // Digits left in the rest of a are confined to the
+            // intersection within b, and the other way around.
+            let ds_a = ds.intersect(other_a);
+            let ds_b = ds.intersect(other_b);
 
-impl Sudoku {
-    pub fn intersection_removal(&mut self) -> bool {
-        for bd in &INDEX_MATRIX {
-            let trio_mat = self.get_trio_mat(bd);
-
-            for x in 0..3 {
-                for y in 0..3 {
-                    let trio = trio_mat[x][y];
-
-                    let (nx1, nx2) = ((x+1)%3, (x+2)%3);
-                    let (ny1, ny2) = ((y+1)%3, (y+2)%3);
-
-                    let other_box = trio_mat[nx1][y].union(trio_mat[nx2][y]);
-                    let other_sec = trio_mat[x][ny1].union(trio_mat[x][ny2]);
-
-                    let ds = trio.intersect(other_box.xor(other_sec));
-
-                    let ds_box = ds.intersect(other_box);
-                    let ds_sec = ds.intersect(other_sec);
-
-                    let mut r = false;
-                    if ds_box.has_digits() {
-                        r = self.handle_intersection(
-                                EntryType::BoxLineReduction,
-                                bd, ds_box,
-                                (x, y), nx1, nx2);
-                    }
-                    if ds_sec.has_digits() {
-                        r = self.handle_intersection(
-                                EntryType::PointedGroup,
-                                bd, ds_sec,
-                                (x, y), ny1, ny2) || r;
-                    }
-
-                    // Early return b/c its prolly quicker overall and
-                    // dealing with cells that may have been updated is
-                    // a hassle.
-                    if r {
-                        return true;
-                    }
-                }
+            let mut r = false;
+            if ds_a.has_digits() {
+                r = self.handle_intersection(
+                        Self::intersection_type(layout.section_type(sb)),
+                        &inter.cells, &inter.rest[0], ds_a);
+            }
+            if ds_b.has_digits() {
+                r = self.handle_intersection(
+                        Self::intersection_type(layout.section_type(sa)),
+                        &inter.cells, &inter.rest[1], ds_b) || r;
+            }
+
+            // Early return b/c its prolly quicker overall and
+            // dealing with cells that may have been updated is
+            // a hassle.
+            if r {
+                return true;
             }
         }
 
         false
     }
 
-    fn get_trio_mat(&self, bd: &[[[usize; 3]; 3]; 3]) -> [[Cell; 3]; 3] {
-        let mut trio_mat = [[CELL_EMPTY; 3]; 3];
+    fn intersection_type(confined_to: SectionType) -> EntryType {
+        match confined_to {
+            SectionType::Box(_) | SectionType::Region(_) => {
+                EntryType::PointedGroup
+            },
+            _ => EntryType::BoxLineReduction,
+        }
+    }
 
-        for x in 0..3 {
-            for y in 0..3 {
-                let mut c = CELL_ACC;
+    // Union of the unsolved cells
+    fn get_union_of(&self, cells: &[CellIndex]) -> Cell {
+        let mut c = CELL_ACC;
 
-                for ci in 0..3 {
-                    let cell = self.cells[bd[x][y][ci]];
-                    if !cell.is_solved() {
-                        c.union_with(cell);
-                    }
-                }
+        for ci in cells {
+            let cell = self.cells[*ci];
 
-                trio_mat[x][y] = c;
+            if !cell.is_solved() {
+                c.union_with(cell);
             }
         }
 
-        trio_mat
+        c
     }
 
-    fn handle_intersection(&mut self, t:  EntryType,
-                                      bd: &[[[usize; 3]; 3]; 3],
-                                      ds: Cell,
-                                      (x, y): (usize, usize),
-                                      id1: usize, id2: usize) -> bool {
-        let to_check = match t {
-            EntryType::BoxLineReduction => [(id1, y), (id2, y)],
-            EntryType::PointedGroup     => [(x, id1), (x, id2)],
-            _ => panic!("{t:?} is not a valid value here"),
-        };
-
-        for (i, j) in to_check {
-            for cid in &bd[i][j] {
-                let cell = &mut self.cells[*cid];
-                
-                if !cell.is_solved() && cell.remove_digits(ds) {
-                    self.register_change(*cid);
-                }
+    fn handle_intersection(&mut self, t: EntryType,
+                                      inter: &[CellIndex],
+                                      rest: &[CellIndex],
+                                      ds: Cell) -> bool {
+        for cid in rest {
+            let cell = &mut self.cells[*cid];
+
+            if !cell.is_solved() && cell.remove_digits(ds) {
+                self.register_change(*cid);
             }
         }
 
@@ -144,10 +116,10 @@ impl Sudoku {
         if r {
             let mut v = Vec::new();
 
-            for c in bd[x][y] {
-                let cell = self.cells[c];
+            for c in inter {
+                let cell = self.cells[*c];
                 if !cell.is_solved() && ds.has_intersection(cell) {
-                    v.push(c);
+                    v.push(*c);
                 }
             }
 
@@ -156,4 +128,4 @@ impl Sudoku {
 
         r
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,273 @@
+use std::sync::Arc;
+
+use crate::Sudoku;
+use crate::cell::{Cell, CELL_ACC};
+use crate::index_manip::*;
+use crate::layout::{Layout, repeats_digit};
+use crate::history::EntryType;
+use crate::sudoku::GroupRule;
+
+// Definition of cage combination:
+// The digits of a cage must add up to its sum without repeating, so any
+// digit that isn't part of a combination that fits in the cage can be
+// removed from it. Combinations are checked against the digits left in
+// each cell, so this keeps working as the cage gets filled in.
+
+// Definition of innie/outie (aka the 45 rule):
+// Every section adds up to the same total (45 on a 9x9 board), and so
+// does any area made of sections that don't overlap. Taking away the
+// cages that are completely inside such an area leaves a few cells
+// (the innies) whose sum is known.
+//
+// If every cell of the area is in a cage, then the cells of those cages
+// that stick out of the area (the outies) have a known sum as well.
+//
+// Both are then treated like a cage, except that cells that don't see
+// each other are allowed to have the same digit.
+
+// Innies/outies with more cells than this rarely remove anything and
+// their combinations are expensive to find, so they are skipped.
+const MAX_SUM_GROUP_SIZE: usize = 4;
+
+
+impl Sudoku {
+    pub fn cage_combination(&mut self) -> bool {
+        let layout = Arc::clone(&self.layout);
+
+        let mut r = false;
+
+        for (ki, cage) in layout.cages().iter().enumerate() {
+            if !self.changes.group_dirty(GroupRule::CageCombination, ki,
+                                         &cage.cells) {
+                continue;
+            }
+
+            let stamp = self.changes.stamp();
+            self.changes.group_checked(GroupRule::CageCombination, ki, stamp);
+
+            if self.restrict_to_sum(EntryType::CageCombination,
+                                    &cage.cells, cage.sum) {
+                r = true;
+            }
+        }
+
+        r
+    }
+
+    pub fn innie_outie(&mut self) -> bool {
+        if !self.layout.has_cages() {
+            return false;
+        }
+
+        let layout = Arc::clone(&self.layout);
+
+        let mut in_area = vec![false; self.cells.len()];
+
+        for (ai, area) in sum_areas(&layout).into_iter().enumerate() {
+            for &ci in &area {
+                in_area[ci] = true;
+            }
+
+            let total = layout.digit_range().sum::<usize>()
+                            * (area.len() / layout.size());
+
+            let mut inside  = 0;
+            let mut outside = 0;
+            let mut innies  = Vec::new();
+            let mut outies  = Vec::new();
+            let mut uncaged = false;
+            let mut seen    = Vec::new();
+
+            for &ci in &area {
+                let Some(ki) = layout.cage_of(ci) else {
+                    uncaged = true;
+                    innies.push(ci);
+                    continue;
+                };
+
+                let cage = &layout.cages()[ki];
+
+                if cage.cells.iter().all(|&c| in_area[c]) {
+                    if !seen.contains(&ki) {
+                        inside += cage.sum;
+                        seen.push(ki);
+                    }
+                    continue;
+                }
+
+                innies.push(ci);
+
+                if !seen.contains(&ki) {
+                    outside += cage.sum;
+                    seen.push(ki);
+
+                    outies.extend(cage.cells.iter().filter(|&&c| !in_area[c]));
+                }
+            }
+
+            for &ci in &area {
+                in_area[ci] = false;
+            }
+
+            // Only the innies and outies themselves are looked at
+            if !self.changes.group_dirty(GroupRule::InnieOutie, ai, &innies)
+                    && !self.changes.group_dirty(GroupRule::InnieOutie, ai, &outies) {
+                continue;
+            }
+
+            let stamp = self.changes.stamp();
+            self.changes.group_checked(GroupRule::InnieOutie, ai, stamp);
+
+            // An impossible sum leaves the cells without any digits,
+            // which the other rules will pick up as a contradiction.
+            if (1..=MAX_SUM_GROUP_SIZE).contains(&innies.len())
+                    && self.restrict_to_sum(EntryType::InnieOutie, &innies,
+                                            total.saturating_sub(inside)) {
+                return true;
+            }
+
+            if !uncaged && (1..=MAX_SUM_GROUP_SIZE).contains(&outies.len())
+                    && self.restrict_to_sum(EntryType::InnieOutie, &outies,
+                                    (inside + outside).saturating_sub(total)) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // Removes every digit that can't be part of a way to fill the cells
+    // so that they add up to sum.
+    fn restrict_to_sum(&mut self, t: EntryType,
+                                  cells: &[CellIndex], sum: usize) -> bool {
+        if cells.iter().all(|&ci| self.cells[ci].is_solved()) {
+            return false;
+        }
+
+        let options = self.sum_options(cells, sum);
+
+        for (&ci, &o) in cells.iter().zip(&options) {
+            let cell = &mut self.cells[ci];
+
+            if !cell.is_solved() && cell.intersect_with(o) {
+                self.register_change(ci);
+            }
+        }
+
+        let mut digits = CELL_ACC;
+
+        for o in options {
+            digits.union_with(o);
+        }
+
+        self.add_history_entry_if_changes(t, cells.to_vec(), digits)
+    }
+
+    // Per cell, the digits it has in at least one way to fill the cells
+    fn sum_options(&self, cells: &[CellIndex], sum: usize) -> Vec<Cell> {
+        let mut search = SumSearch {
+            sudoku: self,
+            cells,
+            digits: vec![0; cells.len()],
+            options: vec![CELL_ACC; cells.len()],
+        };
+
+        search.fill(0, sum);
+
+        search.options
+    }
+}
+
+
+struct SumSearch<'a> {
+    sudoku: &'a Sudoku,
+    cells: &'a [CellIndex],
+    digits: Vec<usize>,
+    options: Vec<Cell>,
+}
+
+impl SumSearch<'_> {
+    fn fill(&mut self, i: usize, left: usize) {
+        if i == self.cells.len() {
+            if left == 0 {
+                for (o, &d) in self.options.iter_mut().zip(&self.digits) {
+                    o.add_digit(d);
+                }
+            }
+
+            return;
+        }
+
+        let remaining = self.cells.len() - i;
+
+        if left < remaining || left > remaining * self.sudoku.size() {
+            return;
+        }
+
+        let ci   = self.cells[i];
+        let cell = self.sudoku.cells[ci];
+
+        for d in self.sudoku.digit_range() {
+            if d > left {
+                break;
+            }
+
+            let possible = if cell.is_solved() {
+                cell.get_number() == d
+            }
+            else {
+                cell.has_digit(d)
+            };
+
+            if !possible || repeats_digit(&self.sudoku.layout, self.cells,
+                                          &self.digits, i, d) {
+                continue;
+            }
+
+            self.digits[i] = d;
+
+            self.fill(i + 1, left - d);
+        }
+    }
+}
+
+
+// Every area of the board whose digits add up to a known total:
+// each section on its own, and every run of neighbouring rows or cols
+// of a board with a single grid.
+fn sum_areas(layout: &Layout) -> Vec<Vec<CellIndex>> {
+    let mut areas = Vec::new();
+
+    let mut rows = Vec::new();
+    let mut cols = Vec::new();
+
+    for si in layout.section_range() {
+        match layout.section_type(si) {
+            SectionType::Row(i) => rows.push((i, si)),
+            SectionType::Col(i) => cols.push((i, si)),
+            _ => (),
+        }
+
+        areas.push(layout.section(si).to_vec());
+    }
+
+    for mut lines in [rows, cols] {
+        // Boards with several grids have overlapping lines
+        if lines.len() != layout.size() {
+            continue;
+        }
+
+        lines.sort();
+
+        for len in 2..lines.len() {
+            for run in lines.windows(len) {
+                areas.push(run.iter()
+                              .flat_map(|&(_, si)| layout.section(si))
+                              .copied()
+                              .collect());
+            }
+        }
+    }
+
+    areas
+}
@@ -1,5 +1,4 @@
 use crate::Sudoku;
-use crate::cell::DIGIT_RANGE;
 use crate::sudoku::EntryType;
 
 impl Sudoku {
@@ -7,9 +6,12 @@ impl Sudoku {
         // A cell has only one or 0 digits left
         // 
         // Struct Memory usage: N/A
+        //
+        // A cell only loses digits when it changes, so only the cells
+        // changed since the last time are looked at.
         let mut r = false;
 
-        for i in 0..81 {
+        for i in self.changes.take_changed() {
             if !self.cells[i].is_solved() {
                 let c = self.cells[i].get_count();
 
@@ -21,7 +23,7 @@ impl Sudoku {
 
                 // if c == 0, then ignore this part
                 if c == 1 {
-                    for d in DIGIT_RANGE {
+                    for d in self.layout.digit_range() {
                         if self.cells[i].has_digit(d) {
                             digit = d;
                             break;
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use crate::Sudoku;
+use crate::history::{EntryType, count_entry_types};
+
+// Puzzles are rated by solving their givens with the rules, the same way
+// solve() does, and adding up the weight (see EntryType::weight) of every
+// step that was taken. Harder techniques weigh more, and so do puzzles
+// that need more steps.
+//
+// A puzzle the rules can't finish has no difficulty, since solving it
+// takes a guess at some point.
+
+
+#[derive(Debug, Clone)]
+pub struct Rating {
+    pub difficulty: Option<usize>,
+    pub techniques: [usize; EntryType::ALL.len()],
+}
+
+/* Structure:
+    difficulty:
+        the sum of the weights of every step, None if the rules got stuck.
+
+    techniques:
+        the amount of steps of each type, in the order of EntryType::ALL.
+        Counts the steps taken before the rules got stuck too.
+*/
+
+
+impl Sudoku {
+    // Solves the givens of the board with the rules and rates the result.
+    // The board itself is left as it is.
+    pub fn rate(&self) -> Rating {
+        let mut s = Sudoku::from_solutions_with(Arc::clone(&self.layout),
+                                                &self.givens());
+
+        s.solve();
+
+        let techniques = count_entry_types(&s.history);
+
+        let difficulty = s.is_valid_solution().then(|| {
+            EntryType::ALL.iter()
+                .zip(techniques)
+                .map(|(t, count)| t.weight() * count)
+                .sum()
+        });
+
+        Rating { difficulty, techniques }
+    }
+}
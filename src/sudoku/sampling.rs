@@ -0,0 +1,145 @@
+use std::sync::Arc;
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::Sudoku;
+use crate::random;
+use crate::layout::Layout;
+use crate::sudoku::Transform;
+
+// A second way to fill a board, with a distribution that can be reasoned
+// about, unlike fill_incremental (where the rules decide which cells are
+// left for the next random choice).
+//
+// Boards of up to 16 cells (4x4) have few enough solutions to list all
+// of them (288 for a plain 4x4 board), so one of them is picked, and
+// every valid board is exactly as likely as any other.
+//
+// Bigger boards are filled by the exact solver, trying the digits of each
+// guess in a random order, and on plain boards a random transform is
+// applied after: a random relabeling, a random order of the rows (within
+// their bands, and of the bands), the same for the cols, and a transpose
+// half the time if the boxes are square. Each of those is picked on its
+// own, so the combined transform is a uniformly random one.
+//
+// That makes the boards uniform within each equivalence class (see
+// canonical.rs): each board is as likely as every other board it is
+// equivalent to, whatever board the solver came up with. So each cell
+// holds each digit equally often, the relabeling alone takes care of that.
+//
+// It is not uniform over all valid boards, since the classes themselves
+// aren't picked in proportion to their size. The solver picks each digit
+// of a guess with the same chance, even if far more boards follow from
+// one digit than from another. (Without the listing, the smaller of the
+// two 4x4 classes would come up a quarter of the time instead of a third.)
+// Variants bigger than 4x4 get no transforms at all, only the solver.
+//
+// tests/sampling.rs checks how often each 4x4 board comes up, from the
+// listing and from the solver (see fill_sampled_by_solver), and the
+// digits in each cell of 9x9 boards. The digits only show that the
+// relabeling works, they would be uniform whatever the solver did.
+
+// Boards with at most this many cells are picked from all their solutions
+const MAX_LISTED_CELLS: usize = 16;
+
+
+impl Sudoku {
+    pub fn fill_sampled() -> Self {
+        Self::fill_sampled_with(Layout::shared_standard())
+            .expect("the standard layout has valid boards")
+    }
+
+    // A filled board with no givens, None if the layout has no valid
+    // board at all.
+    pub fn fill_sampled_with(layout: impl Into<Arc<Layout>>) -> Option<Self> {
+        let mut s = Self::with_layout(layout);
+
+        if s.cells.len() > MAX_LISTED_CELLS {
+            return s.fill_by_solver();
+        }
+
+        let solution = s.find_solutions(usize::MAX)
+            .choose(&mut random::rng())?
+            .clone();
+
+        s.set_solution(&solution);
+
+        Some(s)
+    }
+
+    // Same as fill_sampled_with, but always uses the solver and the
+    // random transforms, even on boards small enough to list. That way
+    // the distribution they give can be checked against all 4x4 boards.
+    pub fn fill_sampled_by_solver(layout: impl Into<Arc<Layout>>) -> Option<Self> {
+        Self::with_layout(layout).fill_by_solver()
+    }
+
+    fn fill_by_solver(mut self) -> Option<Self> {
+        let solution = self.random_solution()?;
+
+        self.set_solution(&solution);
+
+        if !self.layout.is_plain() {
+            return Some(self);
+        }
+
+        for t in self.random_transforms() {
+            self = self.transform(&t, false)?;
+        }
+
+        Some(self)
+    }
+
+    fn set_solution(&mut self, solution: &[usize]) {
+        for (c, &d) in self.cells.iter_mut().zip(solution) {
+            c.solve_cell(d);
+        }
+
+        self.update_section_digit_sum();
+    }
+
+    // A uniformly random transform of a plain board, as a list of
+    // transforms that are applied in order.
+    fn random_transforms(&self) -> Vec<Transform> {
+        let n = self.size();
+        let (box_rows, box_cols) = self.layout.box_shape();
+
+        let mut r = random::rng();
+
+        let mut digits: Vec<usize> = self.layout.digit_range().collect();
+        digits.shuffle(&mut r);
+
+        let mut ts = vec![
+            Transform::Relabel(digits),
+            Transform::PermuteRows(random_line_order(n, box_rows)),
+            Transform::PermuteCols(random_line_order(n, box_cols)),
+        ];
+
+        if box_rows == box_cols && r.gen_bool(0.5) {
+            ts.push(Transform::Transpose);
+        }
+
+        ts
+    }
+}
+
+
+// A random order of n lines that keeps the lines of each group of
+// group_size together, in the format of Transform::PermuteRows.
+fn random_line_order(n: usize, group_size: usize) -> Vec<usize> {
+    let mut r = random::rng();
+
+    let mut groups: Vec<usize> = (0..n / group_size).collect();
+    groups.shuffle(&mut r);
+
+    let mut order = Vec::with_capacity(n);
+
+    for g in groups {
+        let mut lines: Vec<usize> = (g * group_size..(g + 1) * group_size).collect();
+        lines.shuffle(&mut r);
+
+        order.extend(lines);
+    }
+
+    order
+}
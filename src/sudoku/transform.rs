@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use crate::Sudoku;
+use crate::index_manip::CellIndex;
+use crate::history::{HistoryEntry, CellChange};
+
+// Changes to a board that turn a valid sudoku into another valid sudoku
+// with the same amount of solutions, moved along with the cells. So a
+// puzzle with a unique solution can be turned into many puzzles that
+// look nothing alike, and its history can come along with it.
+//
+// The moved history is still a valid way to solve the new board, but it
+// isn't what solve() does on it: the rules go through the sections and
+// cells in order, so they can take other steps (and more or fewer of
+// them), and the new board can get another rating.
+//
+// Only plain boards (see Layout::is_plain) can be transformed, since the
+// sections of variants don't move along with the cells. Turning the board
+// a quarter turn or transposing it also needs square boxes.
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Transform {
+    Relabel(Vec<usize>),
+    PermuteRows(Vec<usize>),
+    PermuteCols(Vec<usize>),
+    Transpose,
+    Rotate,
+    MirrorHorizontal,
+    MirrorVertical,
+}
+
+/* Structure:
+    Relabel:
+        the new digit of each digit, in order (the first one replaces 1).
+        It has to use every digit once.
+
+    PermuteRows, PermuteCols:
+        for each row (col) of the new board, the row (col) of the old one
+        that moves there. The rows of a band have to stay together, so
+        this can reorder the rows within each band and the bands
+        themselves (the same for cols and stacks).
+
+    Transpose:
+        mirrors the board across its main diagonal.
+
+    Rotate:
+        turns the board a quarter turn clockwise.
+
+    MirrorHorizontal:
+        flips the board upside down.
+
+    MirrorVertical:
+        flips the board left to right.
+*/
+
+
+impl Sudoku {
+    // The board after the transform, with the givens, the solved cells
+    // and the digits left in each cell moved along. With history, the
+    // history is moved along as well, otherwise it's left empty.
+    //
+    // None if the board isn't plain, or if the transform doesn't fit it.
+    pub fn transform(&self, t: &Transform, with_history: bool) -> Option<Sudoku> {
+        if !self.layout.is_plain() {
+            return None;
+        }
+
+        let n = self.size();
+
+        let mut digits: Vec<usize> = (0..=n).collect();
+        let mut moved_to: Vec<CellIndex> = (0..n * n).collect();
+
+        match t {
+            Transform::Relabel(new) => {
+                if !is_permutation(new.iter().map(|&d| d.wrapping_sub(1)), n) {
+                    return None;
+                }
+
+                digits[1..].copy_from_slice(new);
+            },
+            Transform::PermuteRows(order) | Transform::PermuteCols(order) => {
+                let (box_rows, box_cols) = self.layout.box_shape();
+
+                let rows = matches!(t, Transform::PermuteRows(_));
+                let group = if rows { box_rows } else { box_cols };
+
+                if !keeps_groups(order, n, group) {
+                    return None;
+                }
+
+                let mut place_of = vec![0; n];
+
+                for (place, &line) in order.iter().enumerate() {
+                    place_of[line] = place;
+                }
+
+                for (ci, m) in moved_to.iter_mut().enumerate() {
+                    let (ri, coi) = (ci / n, ci % n);
+
+                    *m = if rows {
+                        place_of[ri] * n + coi
+                    }
+                    else {
+                        ri * n + place_of[coi]
+                    };
+                }
+            },
+            _ => {
+                let (box_rows, box_cols) = self.layout.box_shape();
+
+                let quarter = matches!(t, Transform::Transpose | Transform::Rotate);
+
+                if quarter && box_rows != box_cols {
+                    return None;
+                }
+
+                for (ci, m) in moved_to.iter_mut().enumerate() {
+                    let (ri, coi) = (ci / n, ci % n);
+
+                    let (nr, nc) = match t {
+                        Transform::Transpose        => (coi, ri),
+                        Transform::Rotate           => (coi, n - 1 - ri),
+                        Transform::MirrorHorizontal => (n - 1 - ri, coi),
+                        _                           => (ri, n - 1 - coi),
+                    };
+
+                    *m = nr * n + nc;
+                }
+            },
+        }
+
+        let mut s = Sudoku::with_layout(Arc::clone(&self.layout));
+
+        for (ci, &m) in moved_to.iter().enumerate() {
+            s.cells[m] = self.cells[ci].relabel(&digits);
+        }
+
+        if with_history {
+            s.history = self.history.iter().map(|h| HistoryEntry {
+                name: h.name,
+                cells: h.cells.iter().map(|&ci| moved_to[ci]).collect(),
+                digits: h.digits.relabel(&digits),
+                changes: h.changes.iter().map(|cc| CellChange {
+                    id: moved_to[cc.id],
+                    new_cell: cc.new_cell.relabel(&digits),
+                }).collect(),
+            }).collect();
+        }
+
+        s.update_section_digit_sum();
+
+        Some(s)
+    }
+}
+
+
+// If the values are 0..n, each once
+fn is_permutation(values: impl Iterator<Item = usize>, n: usize) -> bool {
+    let mut seen = vec![false; n];
+    let mut count = 0;
+
+    for v in values {
+        if v >= n || seen[v] {
+            return false;
+        }
+
+        seen[v] = true;
+        count += 1;
+    }
+
+    count == n
+}
+
+// If order is a permutation of 0..n that keeps the lines of each group
+// of group_size lines together
+fn keeps_groups(order: &[usize], n: usize, group: usize) -> bool {
+    is_permutation(order.iter().copied(), n)
+        && order.chunks(group).all(|c| c.iter().all(|&l| l / group == c[0] / group))
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+
+use crate::Sudoku;
+use crate::cell::MAX_DIGIT;
+use crate::index_manip::*;
+
+// Checks the board directly from its cells, without relying on any of
+// the state kept up to date by solve(). This makes it safe to use on
+// boards that were never solved, such as imported puzzles.
+//
+// Only solved cells with a digit are checked, so a partial board is
+// valid as long as none of its set digits repeat within a section.
+// Killer cages are checked separately, a cage is only wrong once all of
+// its cells are set. Peers that aren't part of a common section (like
+// the cells a knight's move apart on an anti-knight board) are checked
+// separately as well.
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Conflict {
+    pub section: SecIndex,
+    pub section_type: SectionType,
+    pub digit: usize,
+    pub cells: Vec<CellIndex>,
+}
+
+/* Structure:
+    section, section_type:
+        the section the conflict was found in, as its index in the
+        layout of the board and what kind of section it is.
+
+    digit:
+        the digit that was set more than once.
+
+    cells:
+        every cell in the section that has the digit set.
+*/
+
+
+impl Sudoku {
+    pub fn verify(&self) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+
+        for si in self.layout.section_range() {
+            let mut cells_of: [Vec<CellIndex>; MAX_DIGIT + 1] = Default::default();
+
+            for &ci in self.layout.section(si) {
+                cells_of[self.cells[ci].get_number()].push(ci);
+            }
+
+            for d in self.layout.digit_range() {
+                if cells_of[d].len() > 1 {
+                    conflicts.push(Conflict {
+                        section: si,
+                        section_type: self.layout.section_type(si),
+                        digit: d,
+                        cells: std::mem::take(&mut cells_of[d]),
+                    });
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        !self.verify().is_empty()
+    }
+
+    // Every pair of peers that have the same digit set, including the
+    // ones that share a section.
+    pub fn peer_conflicts(&self) -> Vec<(CellIndex, CellIndex)> {
+        let mut pairs = Vec::new();
+
+        for (ci, cell) in self.cells.iter().enumerate() {
+            let d = cell.get_number();
+
+            if d == 0 {
+                continue;
+            }
+
+            for &p in self.layout.peers_of(ci) {
+                if p > ci && self.cells[p].get_number() == d {
+                    pairs.push((ci, p));
+                }
+            }
+        }
+
+        pairs
+    }
+
+    // Index of every constraint whose cells are all set, but with digits
+    // that don't satisfy it.
+    pub fn broken_constraints(&self) -> Vec<usize> {
+        let mut broken = Vec::new();
+
+        for (ki, c) in self.layout.constraints().iter().enumerate() {
+            let digits: Vec<usize> = c.cells().iter()
+                .map(|&ci| self.cells[ci].get_number())
+                .collect();
+
+            if digits.iter().all(|&d| d != 0)
+                    && !c.is_satisfied_by(&digits, self.size()) {
+                broken.push(ki);
+            }
+        }
+
+        broken
+    }
+
+    // Index of every cage that repeats a set digit, or whose cells are
+    // all set but don't add up to its sum.
+    pub fn wrong_cages(&self) -> Vec<usize> {
+        let mut wrong = Vec::new();
+
+        for (ki, cage) in self.layout.cages().iter().enumerate() {
+            let digits: Vec<usize> = cage.cells.iter()
+                .map(|&ci| self.cells[ci].get_number())
+                .collect();
+
+            let repeats = digits.iter().enumerate()
+                .any(|(i, &d)| d != 0 && digits[..i].contains(&d));
+
+            let complete = digits.iter().all(|&d| d != 0);
+
+            if repeats || (complete && digits.iter().sum::<usize>() != cage.sum) {
+                wrong.push(ki);
+            }
+        }
+
+        wrong
+    }
+
+    // Unlike is_valid, this doesn't require the board to be solved.
+    // It returns false for incomplete boards instead.
+    pub fn is_valid_solution(&self) -> bool {
+        self.cells.iter().all(|c| c.get_number() != 0)
+            && self.peer_conflicts().is_empty()
+            && self.wrong_cages().is_empty()
+            && self.broken_constraints().is_empty()
+    }
+}
+
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} has {} set in cells {:?}",
+                  self.section_type, self.digit, self.cells)
+    }
+}
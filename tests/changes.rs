@@ -0,0 +1,40 @@
+use simple_sudoku_generator::layout::Layout;
+use simple_sudoku_generator::sudoku::{ExactSolution, Sudoku};
+
+// Cells set from outside the rules after solve() has run have to be
+// looked at by the next solve(), even though the rules skip the parts of
+// the board that didn't change (see changes.rs).
+
+// A puzzle with a unique solution that the rules can't finish
+const HARD_PUZZLE: &str =
+    "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+
+
+#[test]
+fn set_cell_after_solve() {
+    let mut s = Sudoku::from_puzzle_str(HARD_PUZZLE).expect("valid puzzle");
+
+    let ExactSolution::Unique(solution) = s.solve_exact() else {
+        panic!("the puzzle has a unique solution");
+    };
+
+    s.solve();
+
+    assert!(!s.is_solved(), "the rules shouldn't finish this puzzle");
+
+    let ci = s.cells().iter().position(|c| !c.is_solved())
+        .expect("an unsolved cell");
+
+    let mut cell = s.cells()[ci];
+    cell.solve_cell(solution[ci]);
+
+    s.set_cell(ci, cell);
+    s.solve();
+
+    for &p in Layout::standard().peers_of(ci) {
+        let c = s.cells()[p];
+
+        assert!(c.is_solved() || !c.has_digit(solution[ci]),
+                "peer {p} of cell {ci} still has {}", solution[ci]);
+    }
+}
@@ -0,0 +1,45 @@
+use simple_sudoku_generator::constraint::{parse_constraints, Constraint, KropkiDot};
+use simple_sudoku_generator::layout::Layout;
+
+// Markings that can't be drawn on the board have to be rejected when
+// they're parsed, instead of turning into constraints that check cells
+// the marking never touched.
+
+
+fn parse(s: &str) -> Option<Vec<Constraint>> {
+    parse_constraints(s, &Layout::standard())
+}
+
+
+#[test]
+fn kropki_next_to() {
+    assert_eq!(parse("white r1c1 r1c2"),
+               Some(vec![Constraint::Kropki(KropkiDot::White, [0, 1])]));
+    assert_eq!(parse("black r1c1 r2c1"),
+               Some(vec![Constraint::Kropki(KropkiDot::Black, [0, 9])]));
+}
+
+#[test]
+fn kropki_apart() {
+    assert_eq!(parse("white r1c1 r9c9"), None);
+    assert_eq!(parse("black r1c1 r2c2"), None);
+    assert_eq!(parse("white r1c1 r1c1"), None);
+}
+
+#[test]
+fn thermometer_path() {
+    assert_eq!(parse("thermo r1c1 r1c2 r2c3"),
+               Some(vec![Constraint::Thermometer(vec![0, 1, 11])]));
+}
+
+#[test]
+fn thermometer_repeats() {
+    assert_eq!(parse("thermo r1c1 r1c2 r1c1"), None);
+    assert_eq!(parse("thermo r1c1 r1c2 r2c2 r2c1 r1c1"), None);
+}
+
+#[test]
+fn thermometer_gap() {
+    assert_eq!(parse("thermo r1c1 r1c3"), None);
+    assert_eq!(parse("thermo r1c1 r1c2 r5c5"), None);
+}
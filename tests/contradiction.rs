@@ -0,0 +1,60 @@
+use simple_sudoku_generator::constraint::Constraint;
+use simple_sudoku_generator::layout::Layout;
+use simple_sudoku_generator::sudoku::{ContradictionKind, Sudoku};
+
+// Every rule of the layout has to be able to report a contradiction, not
+// only the sections: peers outside of a section, cages and constraints.
+
+
+// A board of the layout with only the given digits set
+fn board(layout: Layout, digits: &[(usize, usize)]) -> Sudoku {
+    let mut sol_arr = vec![0; layout.cell_count()];
+
+    for &(ci, d) in digits {
+        sol_arr[ci] = d;
+    }
+
+    Sudoku::from_solutions_with(layout, &sol_arr)
+}
+
+
+#[test]
+fn repeated_anti_knight_peer() {
+    let mut layout = Layout::standard();
+    layout.add_anti_knight();
+
+    // r1c3 and r2c5 are a knight's move apart, in different boxes
+    let s = board(layout, &[(2, 1), (13, 1)]);
+
+    assert!(s.get_contradictions().contains(&ContradictionKind::RepeatedPeer(2, 13)));
+}
+
+#[test]
+fn section_repeat_isnt_a_peer_repeat() {
+    let s = board(Layout::standard(), &[(0, 1), (1, 1)]);
+
+    let kinds = s.get_contradictions();
+
+    assert!(kinds.iter().any(|k| matches!(k, ContradictionKind::RepeatedDigit(..))));
+    assert!(!kinds.iter().any(|k| matches!(k, ContradictionKind::RepeatedPeer(..))));
+}
+
+#[test]
+fn wrong_cage() {
+    let mut layout = Layout::standard();
+    layout.add_cage(vec![0, 1], 5);
+
+    let s = board(layout, &[(0, 1), (1, 2)]);
+
+    assert!(s.get_contradictions().contains(&ContradictionKind::WrongCage(0)));
+}
+
+#[test]
+fn broken_constraint() {
+    let mut layout = Layout::standard();
+    layout.add_constraint(Constraint::LessThan([0, 1]));
+
+    let s = board(layout, &[(0, 2), (1, 1)]);
+
+    assert!(s.get_contradictions().contains(&ContradictionKind::BrokenConstraint(0)));
+}
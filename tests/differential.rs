@@ -0,0 +1,138 @@
+use std::sync::Arc;
+use rand::seq::SliceRandom;
+
+use simple_sudoku_generator::layout::Layout;
+use simple_sudoku_generator::random;
+use simple_sudoku_generator::sudoku::{DifferentialError, Sudoku, Symmetry};
+
+// Runs the rules against the exact solver (see differential.rs) on known
+// hard puzzles and on puzzles made from generated boards. Puzzles the
+// rules can't finish are fine, but every elimination they make has to
+// agree with the solution.
+
+const PUZZLE_CORPUS: &[&str] = &[
+    "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......",
+    "52...6.........7.13...........4..8..6......5...........418.........3..2...87.....",
+    "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+    "..53.....8......2..7..1.5..4....53...1..7...6..32...8..6.5....9..4....3......97..",
+    "1....7.9..3..2...8..96..5....53..9...1..8...26....4...3......1..4......7..7...3..",
+];
+
+// Clues kept by random_puzzle, too few to always leave a unique solution
+const PUZZLE_CLUES: usize = 32;
+
+const GENERATED_BOARDS: usize = 100;
+
+
+// Panics if the rules made an elimination the solution disagrees with,
+// or if the puzzle had no solution to begin with.
+fn check(puzzle: &Sudoku) {
+    match puzzle.differential_check() {
+        Ok(_) | Err(DifferentialError::Unsolved) => (),
+        Err(DifferentialError::NoSolution) => {
+            panic!("No solution for puzzle:\n{puzzle}");
+        },
+        Err(DifferentialError::BadElimination(b)) => {
+            panic!("{b}\nPuzzle:\n{puzzle}");
+        },
+    }
+}
+
+// Keeps PUZZLE_CLUES random cells of a filled board
+fn random_puzzle(sud: &Sudoku) -> Sudoku {
+    let mut sol_arr = sud.to_solutions();
+
+    let mut order: Vec<usize> = (0..sol_arr.len()).collect();
+    order.shuffle(&mut random::rng());
+
+    for &i in &order[PUZZLE_CLUES.min(order.len())..] {
+        sol_arr[i] = 0;
+    }
+
+    Sudoku::from_solutions_with(sud.shared_layout(), &sol_arr)
+}
+
+// Checks puzzles made from GENERATED_BOARDS boards of the layout, both
+// with random clues and with unique solutions
+fn check_generated(layout: Layout, seed: u64) {
+    random::set_seed(seed);
+
+    let layout = Arc::new(layout);
+
+    for _ in 0..GENERATED_BOARDS {
+        let sud = Sudoku::fill_incremental_with(Arc::clone(&layout));
+
+        if !sud.is_valid_solution() {
+            continue;
+        }
+
+        check(&random_puzzle(&sud));
+        check(&sud.remove_clues(Symmetry::None));
+    }
+}
+
+
+#[test]
+fn corpus() {
+    for p in PUZZLE_CORPUS {
+        check(&Sudoku::from_puzzle_str(p).expect("invalid corpus puzzle"));
+    }
+}
+
+#[test]
+fn generated_standard() {
+    check_generated(Layout::standard(), 30);
+}
+
+#[test]
+fn generated_jigsaw() {
+    let regions = "\
+        111222333\
+        111222333\
+        111225336\
+        444255366\
+        444555666\
+        444555696\
+        778888996\
+        777889999\
+        777788899";
+
+    check_generated(Layout::from_region_str(regions).expect("invalid regions"), 30);
+}
+
+// 5x5 has no boxes to fall back on
+#[test]
+fn generated_jigsaw_5x5() {
+    let regions = "\
+        11122\
+        11222\
+        33344\
+        33444\
+        55555";
+
+    check_generated(Layout::from_region_str(regions).expect("invalid regions"), 30);
+}
+
+#[test]
+fn generated_x_sudoku() {
+    check_generated(Layout::x_sudoku(), 30);
+}
+
+#[test]
+fn generated_anti_knight() {
+    let mut layout = Layout::standard();
+    layout.add_anti_knight();
+
+    check_generated(layout, 30);
+}
+
+#[test]
+fn killer() {
+    random::set_seed(30);
+
+    for _ in 0..20 {
+        let sud = Sudoku::fill_killer(4).expect("4 cell cages are allowed");
+
+        check(&Sudoku::with_layout(sud.shared_layout()));
+    }
+}
@@ -0,0 +1,59 @@
+use simple_sudoku_generator::random;
+use simple_sudoku_generator::sudoku::Sudoku;
+
+// Filling a board around givens has to keep every given and end up with a
+// valid solution, even when the rules alone can't solve the puzzle.
+
+// A puzzle with a unique solution that the rules can't finish
+const HARD_PUZZLE: &str =
+    "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+
+
+fn check_filled(givens: &[usize]) {
+    let s = Sudoku::fill_from_givens(givens).expect("the givens have a solution");
+
+    assert!(s.is_valid_solution(), "invalid board:\n{s}");
+
+    for (&g, d) in givens.iter().zip(s.to_solutions()) {
+        assert!(g == 0 || g == d, "a given was changed:\n{s}");
+    }
+}
+
+
+#[test]
+fn hard_puzzle() {
+    random::set_seed(40);
+
+    let givens = Sudoku::from_puzzle_str(HARD_PUZZLE)
+        .expect("valid puzzle")
+        .givens();
+
+    for _ in 0..20 {
+        check_filled(&givens);
+    }
+}
+
+#[test]
+fn first_row() {
+    random::set_seed(40);
+
+    let mut givens = vec![0; 81];
+
+    for (i, g) in givens.iter_mut().take(9).enumerate() {
+        *g = i + 1;
+    }
+
+    for _ in 0..20 {
+        check_filled(&givens);
+    }
+}
+
+#[test]
+fn impossible_givens() {
+    let mut givens = vec![0; 81];
+
+    givens[0] = 1;
+    givens[1] = 1;
+
+    assert!(Sudoku::fill_from_givens(&givens).is_none());
+}
@@ -0,0 +1,58 @@
+use simple_sudoku_generator::random;
+use simple_sudoku_generator::sudoku::{Sudoku, Symmetry};
+
+// Minimizing a puzzle that was already solved (fully or partly) has to
+// start from its givens, not from the solved cells, so the minimal
+// puzzle only keeps clues the original puzzle had.
+
+// A puzzle with a unique solution that the rules can't finish
+const HARD_PUZZLE: &str =
+    "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+
+
+fn check_minimized(puzzle: &Sudoku) {
+    let givens = puzzle.givens();
+
+    let m = puzzle.minimize(3).expect("the puzzle has a unique solution");
+
+    assert!(m.has_unique_solution(), "not unique:\n{m}");
+
+    for (&g, d) in givens.iter().zip(m.givens()) {
+        assert!(d == 0 || d == g, "a clue that wasn't given was kept:\n{m}");
+    }
+}
+
+
+#[test]
+fn after_partial_solve() {
+    random::set_seed(42);
+
+    let mut s = Sudoku::from_puzzle_str(HARD_PUZZLE).expect("valid puzzle");
+
+    s.solve();
+
+    check_minimized(&s);
+}
+
+#[test]
+fn generated_after_solve() {
+    random::set_seed(42);
+
+    let mut tried = 0;
+
+    while tried < 5 {
+        let sud = Sudoku::fill_incremental();
+
+        if !sud.is_valid_solution() {
+            continue;
+        }
+
+        tried += 1;
+
+        let mut puzzle = sud.remove_clues(Symmetry::None);
+
+        puzzle.solve();
+
+        check_minimized(&puzzle);
+    }
+}
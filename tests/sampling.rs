@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use simple_sudoku_generator::layout::Layout;
+use simple_sudoku_generator::random;
+use simple_sudoku_generator::stats::{chi_square, chi_square_z};
+use simple_sudoku_generator::sudoku::Sudoku;
+
+// Checks the boards made by sampling.rs. The seeds are fixed, so these
+// always see the same boards. A z above 4 happens about once in 30000
+// runs by chance alone.
+
+const MAX_Z: f64 = 4.0;
+
+// The amount of valid 4x4 boards
+const FOUR_BY_FOUR_BOARDS: usize = 288;
+
+
+// The relabeling alone makes every digit equally likely in every cell,
+// so this only catches a broken relabeling (or a sampler that skips it),
+// not a bias in the boards the solver comes up with. That's left to
+// solver_classes_match_their_ratio.
+#[test]
+fn digits_per_cell_are_uniform() {
+    random::set_seed(49);
+
+    let layout = Layout::shared_standard();
+    let mut counts = vec![0; 81 * 9];
+
+    for _ in 0..1000 {
+        let s = Sudoku::fill_sampled_with(Arc::clone(&layout))
+            .expect("the standard layout has valid boards");
+
+        assert!(s.is_valid_solution());
+
+        for (ci, d) in s.to_solutions().into_iter().enumerate() {
+            counts[ci * 9 + d - 1] += 1;
+        }
+    }
+
+    let z = chi_square_z(chi_square(&counts), 81 * 8);
+
+    assert!(z < MAX_Z, "digits per cell aren't uniform, z = {z:.2}");
+}
+
+#[test]
+fn bigger_boards_are_valid() {
+    random::set_seed(49);
+
+    let layouts = [
+        Layout::x_sudoku(),
+        Layout::of_size(6).expect("6x6 is a valid size"),
+    ];
+
+    for layout in layouts {
+        let layout = Arc::new(layout);
+
+        for _ in 0..100 {
+            let s = Sudoku::fill_sampled_with(Arc::clone(&layout))
+                .expect("the layout has valid boards");
+
+            assert!(s.is_valid_solution(), "invalid board:\n{s}");
+        }
+    }
+}
+
+// 4x4 boards small enough to be listed still go through the solver here,
+// the same way 9x9 boards do. Its boards should be uniform within each
+// equivalence class, with the smaller class a quarter of the time.
+#[test]
+fn solver_classes_match_their_ratio() {
+    random::set_seed(49);
+
+    let layout = Arc::new(Layout::of_size(4).expect("4x4 is a valid size"));
+
+    // The class of every 4x4 board, by its canonical form
+    let mut class_of = HashMap::new();
+
+    for sol in Sudoku::with_layout(Arc::clone(&layout)).find_solutions(usize::MAX) {
+        let s = Sudoku::from_solutions_with(Arc::clone(&layout), &sol);
+        let form = s.canonical_form().expect("4x4 boards are plain");
+
+        class_of.insert(sol, form);
+    }
+
+    assert_eq!(class_of.len(), FOUR_BY_FOUR_BOARDS);
+
+    let mut counts: HashMap<Vec<usize>, usize> =
+        class_of.keys().map(|sol| (sol.clone(), 0)).collect();
+
+    let draws = FOUR_BY_FOUR_BOARDS * 20;
+
+    for _ in 0..draws {
+        let s = Sudoku::fill_sampled_by_solver(Arc::clone(&layout))
+            .expect("4x4 layouts have valid boards");
+
+        assert!(s.is_valid_solution());
+
+        *counts.get_mut(&s.to_solutions()).expect("a valid board") += 1;
+    }
+
+    let mut classes: HashMap<&Vec<usize>, Vec<usize>> = HashMap::new();
+
+    for (sol, form) in &class_of {
+        classes.entry(form).or_default().push(counts[sol]);
+    }
+
+    let mut classes: Vec<Vec<usize>> = classes.into_values().collect();
+    classes.sort_by_key(|c| c.len());
+
+    assert_eq!(classes.len(), 2, "4x4 boards have two classes");
+
+    for class in &classes {
+        let z = chi_square_z(chi_square(class), class.len() - 1);
+
+        assert!(z < MAX_Z, "boards within a class aren't uniform, z = {z:.2}");
+    }
+
+    // The smaller class against its expected share of the draws
+    let (n, p) = (draws as f64, 0.25);
+    let smaller: usize = classes[0].iter().sum();
+    let z = (smaller as f64 - n * p) / (n * p * (1.0 - p)).sqrt();
+
+    assert!(z.abs() < MAX_Z,
+            "the smaller class came up {smaller} of {draws} times, z = {z:.2}");
+}
+
+#[test]
+fn four_by_four_boards_are_uniform() {
+    random::set_seed(49);
+
+    let layout = Arc::new(Layout::of_size(4).expect("4x4 is a valid size"));
+    let mut counts = HashMap::new();
+
+    for _ in 0..FOUR_BY_FOUR_BOARDS * 20 {
+        let s = Sudoku::fill_sampled_with(Arc::clone(&layout))
+            .expect("4x4 layouts have valid boards");
+
+        assert!(s.is_valid_solution());
+
+        *counts.entry(s.to_solutions()).or_insert(0) += 1;
+    }
+
+    // Every board that never came up counts as well
+    let mut counts: Vec<usize> = counts.into_values().collect();
+
+    assert!(counts.len() <= FOUR_BY_FOUR_BOARDS);
+    counts.resize(FOUR_BY_FOUR_BOARDS, 0);
+
+    let z = chi_square_z(chi_square(&counts), FOUR_BY_FOUR_BOARDS - 1);
+
+    assert!(z < MAX_Z, "4x4 boards aren't uniform, z = {z:.2}");
+}